@@ -1,17 +1,82 @@
+#[cfg(feature = "log")]
+use std::ffi::OsStr;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::io;
+use std::io::Write;
+use std::mem;
+#[cfg(any(doc, unix))]
+use std::os::raw::c_int;
 use std::panic;
+#[attr_alias::eval]
+#[attr_alias(procfs, cfg(any(doc, *)))]
+use std::path::Path;
 use std::process::Child;
+use std::sync::mpsc;
 use std::thread;
+use std::thread::JoinHandle;
 use std::time::Duration;
+use std::time::Instant;
 
 use super::imp;
+use super::Capture;
 use super::Control;
+use super::Decision;
+use super::Event;
+use super::Events;
 use super::ExitStatus;
+#[cfg(any(doc, unix))]
+use super::ExtraPipe;
 use super::Output;
+#[cfg(any(doc, unix))]
+use super::PauseHandle;
 use super::PipeFilter;
+use super::Progress;
+#[attr_alias::eval]
+#[attr_alias(unix_pty, cfg(any(doc, *)))]
+use super::Pty;
+use super::Report;
 use super::WaitResult;
 
 mod pipe;
 pub(super) use pipe::Pipe;
+pub(super) use pipe::ReadBuffer;
+
+mod timer;
+use timer::SoftTimeLimit;
+use timer::Tick;
+use timer::Timer;
+
+mod trace;
+use trace::event;
+
+#[cfg(unix)]
+mod session;
+#[cfg(unix)]
+pub(super) use session::Session;
+
+struct Handler(Box<dyn FnOnce(u32) -> io::Result<()> + Send>);
+
+impl Handler {
+    fn new<F>(handler: F) -> Self
+    where
+        F: 'static + FnOnce(u32) -> io::Result<()> + Send,
+    {
+        Self(Box::new(handler))
+    }
+
+    fn call(self, pid: u32) -> io::Result<()> {
+        (self.0)(pid)
+    }
+}
+
+impl Debug for Handler {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handler").finish_non_exhaustive()
+    }
+}
 
 #[attr_alias::eval]
 #[derive(Debug)]
@@ -19,20 +84,50 @@ struct Options {
     #[attr_alias(memory_limit)]
     memory_limit: Option<usize>,
     time_limit: Option<Duration>,
-    stdout_filter: Option<pipe::FilterWrapper>,
-    stderr_filter: Option<pipe::FilterWrapper>,
+    soft_time_limit: Option<SoftTimeLimit>,
+    tick: Option<Tick>,
+    before_terminate: Option<Handler>,
+    #[cfg(unix)]
+    process_tree: Option<io::Result<imp::ProcessTree>>,
+    #[attr_alias(procfs)]
+    cgroup: Option<io::Result<imp::Cgroup>>,
+    #[cfg(any(doc, unix))]
+    forward_signals: Option<(Vec<c_int>, Duration)>,
+    #[cfg(unix)]
+    pause_handle: Option<PauseHandle>,
+    #[cfg(unix)]
+    exclude_paused_time: bool,
+    #[attr_alias(procfs)]
+    subreaper: Option<io::Result<imp::Subreaper>>,
+    #[attr_alias(procfs)]
+    leftovers: Option<(io::Result<imp::Leftovers>, bool)>,
+    stdout: pipe::Options<'static>,
+    stderr: pipe::Options<'static>,
+    extra_pipes: Vec<(imp::OwnedFd, pipe::Options<'static>)>,
 }
 
-pub(super) trait Process {
+type PipesResult = io::Result<Vec<ReadBuffer>>;
+
+pub(super) trait Process: Debug {
     type Result: AsRef<ExitStatus>;
 
     fn get(&mut self) -> &mut Child;
 
+    fn prepend_stdout(result: Self::Result, stdout: Vec<u8>) -> Self::Result;
+
+    #[allow(private_interfaces)]
+    fn take_pipes(
+        &mut self,
+        options: &mut Options,
+    ) -> Option<Vec<Option<Pipe<'static>>>>;
+
     #[allow(private_interfaces)]
-    fn run_wait(&mut self, options: Options) -> WaitResult<Self::Result>;
+    fn new_result(
+        status: ExitStatus,
+        buffers: Vec<ReadBuffer>,
+    ) -> Self::Result;
 }
 
-#[attr_alias::eval]
 impl Process for &mut Child {
     type Result = ExitStatus;
 
@@ -40,28 +135,36 @@ impl Process for &mut Child {
         self
     }
 
-    fn run_wait(&mut self, options: Options) -> WaitResult<Self::Result> {
-        let result = self.try_wait();
-        if let Ok(Some(exit_status)) = result {
-            return Ok(Some(exit_status.into()));
-        }
+    fn prepend_stdout(result: Self::Result, _: Vec<u8>) -> Self::Result {
+        result
+    }
 
-        let mut handle = imp::Process::new(self);
-        #[attr_alias(memory_limit)]
-        if let Some(memory_limit) = options.memory_limit {
-            handle.set_memory_limit(memory_limit)?;
+    #[allow(private_interfaces)]
+    fn take_pipes(
+        &mut self,
+        options: &mut Options,
+    ) -> Option<Vec<Option<Pipe<'static>>>> {
+        // Pipes are only read when their output is used.
+        macro_rules! pipe {
+            ( $pipe:ident ) => {{
+                if options.$pipe.is_used() {
+                    let mut options =
+                        mem::replace(&mut options.$pipe, pipe::Options::new());
+                    options.discard = true;
+                    self.$pipe.take().map(|x| Pipe::new(x.into(), options))
+                } else {
+                    None
+                }
+            }};
         }
-        let result = handle.wait(options.time_limit)?;
-        result
-            .map(|result| {
-                self.try_wait().map(|std_result| {
-                    ExitStatus::new(
-                        result,
-                        std_result.expect("missing exit status"),
-                    )
-                })
-            })
-            .transpose()
+
+        let pipes = [pipe!(stdout), pipe!(stderr)];
+        pipes.iter().any(Option::is_some).then(|| pipes.into())
+    }
+
+    #[allow(private_interfaces)]
+    fn new_result(status: ExitStatus, _: Vec<ReadBuffer>) -> Self::Result {
+        status
     }
 }
 
@@ -72,33 +175,100 @@ impl Process for Child {
         self
     }
 
+    fn prepend_stdout(
+        mut result: Self::Result,
+        mut stdout: Vec<u8>,
+    ) -> Self::Result {
+        stdout.append(&mut result.stdout);
+        result.stdout = stdout;
+        result
+    }
+
     #[allow(private_interfaces)]
-    fn run_wait(&mut self, mut options: Options) -> WaitResult<Self::Result> {
+    fn take_pipes(
+        &mut self,
+        options: &mut Options,
+    ) -> Option<Vec<Option<Pipe<'static>>>> {
         macro_rules! pipe {
-            ( $pipe:ident , $filter:ident ) => {{
-                let filter = options.$filter.take();
-                self.$pipe.take().map(|x| Pipe::new(x.into(), filter))
+            ( $pipe:ident ) => {{
+                let options =
+                    mem::replace(&mut options.$pipe, pipe::Options::new());
+                self.$pipe.take().map(|x| Pipe::new(x.into(), options))
             }};
         }
 
-        let pipes =
-            [pipe!(stdout, stdout_filter), pipe!(stderr, stderr_filter)];
-        let reader =
-            thread::Builder::new().spawn(move || imp::read2(pipes))?;
-
-        (&mut &mut *self)
-            .run_wait(options)?
-            .map(|status| {
-                reader
-                    .join()
-                    .unwrap_or_else(|x| panic::resume_unwind(x))
-                    .map(|[stdout, stderr]| Output {
-                        status,
-                        stdout,
-                        stderr,
-                    })
-            })
-            .transpose()
+        let mut pipes = vec![pipe!(stdout), pipe!(stderr)];
+        pipes.extend(
+            options
+                .extra_pipes
+                .drain(..)
+                .map(|(pipe, options)| Some(Pipe::new(pipe, options))),
+        );
+        Some(pipes)
+    }
+
+    #[allow(private_interfaces)]
+    fn new_result(
+        status: ExitStatus,
+        buffers: Vec<ReadBuffer>,
+    ) -> Self::Result {
+        let [stdout, stderr] = buffers.try_into().expect("missing buffers");
+        Output {
+            status,
+            stdout: stdout.inner,
+            stderr: stderr.inner,
+        }
+    }
+}
+
+fn join_reader(reader: JoinHandle<PipesResult>) -> PipesResult {
+    reader.join().unwrap_or_else(|x| panic::resume_unwind(x))
+}
+
+// Sessions read all pipes except stdout while they are running, so only
+// stdout is read by the later reader.
+#[cfg(unix)]
+fn join_session_reader(
+    session_reader: JoinHandle<PipesResult>,
+    buffers: Option<PipesResult>,
+) -> PipesResult {
+    let mut session_buffers = join_reader(session_reader)?;
+    if let Some(stdout) =
+        buffers.transpose()?.and_then(|x| x.into_iter().next())
+    {
+        session_buffers[0] = stdout;
+    }
+    Ok(session_buffers)
+}
+
+#[derive(Debug)]
+pub(super) struct BorrowedChild<'a>(pub(super) &'a mut Child);
+
+impl Process for BorrowedChild<'_> {
+    type Result = Output;
+
+    fn get(&mut self) -> &mut Child {
+        self.0
+    }
+
+    fn prepend_stdout(result: Self::Result, stdout: Vec<u8>) -> Self::Result {
+        <Child as Process>::prepend_stdout(result, stdout)
+    }
+
+    #[allow(private_interfaces)]
+    fn take_pipes(
+        &mut self,
+        options: &mut Options,
+    ) -> Option<Vec<Option<Pipe<'static>>>> {
+        self.0.take_pipes(options)
+    }
+
+    #[allow(private_interfaces)]
+    fn new_result(
+        status: ExitStatus,
+        buffers: Vec<ReadBuffer>,
+    ) -> Self::Result {
+        <Child as Process>::new_result(status, buffers)
     }
 }
 
@@ -111,6 +281,10 @@ where
     options: Options,
     strict_errors: bool,
     terminate_for_timeout: bool,
+    start: Instant,
+    report: Report<()>,
+    #[cfg(unix)]
+    session_reader: Option<JoinHandle<PipesResult>>,
 }
 
 #[attr_alias::eval]
@@ -118,19 +292,278 @@ impl<P> Buffer<P>
 where
     P: Process,
 {
-    pub(super) const fn new(process: P) -> Self {
+    pub(super) fn new(process: P) -> Self {
         Self {
             process,
             options: Options {
                 #[attr_alias(memory_limit)]
                 memory_limit: None,
                 time_limit: None,
-                stdout_filter: None,
-                stderr_filter: None,
+                soft_time_limit: None,
+                tick: None,
+                before_terminate: None,
+                #[cfg(unix)]
+                process_tree: None,
+                #[attr_alias(procfs)]
+                cgroup: None,
+                #[cfg(any(doc, unix))]
+                forward_signals: None,
+                #[cfg(unix)]
+                pause_handle: None,
+                #[cfg(unix)]
+                exclude_paused_time: false,
+                #[attr_alias(procfs)]
+                subreaper: None,
+                #[attr_alias(procfs)]
+                leftovers: None,
+                stdout: pipe::Options::new(),
+                stderr: pipe::Options::new(),
+                extra_pipes: Vec::new(),
             },
             strict_errors: false,
             terminate_for_timeout: false,
+            start: Instant::now(),
+            report: Report::new(None),
+            #[cfg(unix)]
+            session_reader: None,
+        }
+    }
+
+    fn terminate(&mut self) -> io::Result<()> {
+        let process = self.process.get();
+        // The identifier cannot be reused until the process is reaped. If
+        // the check fails, the process is assumed to be running, since it
+        // must still be terminated.
+        let (running, mut result) = match process.try_wait() {
+            Ok(exit_status) => (exit_status.is_none(), Ok(())),
+            Err(error) => (true, Err(error)),
+        };
+        if let Some(handler) = self.options.before_terminate.take() {
+            if running {
+                event!("running termination hook");
+                let next_result = handler.call(process.id());
+                if result.is_ok() {
+                    result = next_result;
+                }
+            }
+        }
+        #[cfg(unix)]
+        if let Some(process_tree) = self.options.process_tree.take() {
+            if running {
+                let next_result =
+                    process_tree.and_then(|x| x.terminate()).map(|_method| {
+                        event!(method = _method, "terminated process tree");
+                    });
+                if result.is_ok() {
+                    result = next_result;
+                }
+            }
+        }
+
+        event!("terminating process");
+        let next_result = process.kill().and_then(|()| {
+            let _exit_status = process.wait()?;
+            event!(exit_status = %_exit_status, "reaped process");
+            Ok(())
+        });
+        if result.is_ok() {
+            result = next_result;
         }
+        let next_result = self.reap_orphans();
+        if result.is_ok() {
+            result = next_result;
+        }
+        result
+    }
+
+    // Errors from builder methods are reported before waiting.
+    fn check_options(&mut self) -> io::Result<()> {
+        #[attr_alias(procfs)]
+        if let Some(cgroup) = self.options.cgroup.take() {
+            let cgroup = cgroup?;
+            // The process group is not needed to terminate the process tree.
+            self.options.process_tree = Some(Ok(cgroup.clone().into()));
+            self.options.cgroup = Some(Ok(cgroup));
+        }
+        #[cfg(unix)]
+        if let Some(process_tree) = self.options.process_tree.take() {
+            self.options.process_tree = Some(Ok(process_tree?));
+        }
+        #[attr_alias(procfs)]
+        if let Some(subreaper) = self.options.subreaper.take() {
+            self.options.subreaper = Some(Ok(subreaper?));
+        }
+        #[attr_alias(procfs)]
+        if let Some((leftovers, terminate)) = self.options.leftovers.take() {
+            self.options.leftovers = Some((Ok(leftovers?), terminate));
+        }
+        Ok(())
+    }
+
+    // The cgroup is only frozen if the caller chose it.
+    #[cfg(unix)]
+    fn find_pause_tree(&mut self) -> io::Result<imp::ProcessTree> {
+        #[attr_alias(procfs)]
+        if let Some(Ok(cgroup)) = &self.options.cgroup {
+            return Ok(cgroup.clone().into());
+        }
+        imp::ProcessTree::find(self.process.get())
+    }
+
+    fn reap_orphans(&mut self) -> io::Result<()> {
+        #[attr_alias(procfs)]
+        if let Some(subreaper) = self.options.subreaper.take() {
+            subreaper?.reap()?;
+            event!("reaped orphaned descendants");
+        }
+        Ok(())
+    }
+
+    fn span(&mut self) -> trace::Span {
+        #[attr_alias(memory_limit)]
+        let memory_limit = self.options.memory_limit;
+        #[attr_alias(memory_limit, cfg(not(*)))]
+        let memory_limit = None;
+        trace::Span::new(
+            self.process.get().id(),
+            self.options.time_limit,
+            memory_limit,
+            self.terminate_for_timeout,
+        )
+    }
+
+    fn run_wait<F>(
+        &mut self,
+        join_fn: F,
+    ) -> WaitResult<<Self as Control>::Result>
+    where
+        F: FnOnce() -> Option<PipesResult>,
+    {
+        self.check_options()?;
+        #[cfg(unix)]
+        let pause = match self.options.pause_handle.take() {
+            Some(pause_handle) => match self.find_pause_tree() {
+                Ok(process_tree) => Some((pause_handle, process_tree)),
+                Err(error) if self.strict_errors => return Err(error),
+                Err(_error) => {
+                    event!(error = %_error, "failed to find process tree");
+                    None
+                }
+            },
+            None => None,
+        };
+        let process = self.process.get();
+        let pid = process.id();
+        let result = process.try_wait();
+        let mut status: ExitStatus = if let Ok(Some(exit_status)) = result {
+            exit_status.into()
+        } else {
+            let mut handle = imp::Process::new(process);
+            #[attr_alias(memory_limit)]
+            if let Some(memory_limit) = self.options.memory_limit {
+                handle.set_memory_limit(memory_limit)?;
+                event!(memory_limit, "applied memory limit");
+            }
+            let mut timer = Timer::new(
+                self.start,
+                pid,
+                self.options.time_limit,
+                self.options.soft_time_limit.take(),
+                self.options.tick.take(),
+            );
+            #[cfg(unix)]
+            if let Some((signals, grace_period)) =
+                self.options.forward_signals.take()
+            {
+                let forwarder = imp::SignalForwarder::new(&signals)?;
+                timer.forward_signals(forwarder, grace_period);
+            }
+            #[cfg(unix)]
+            if let Some((pause_handle, process_tree)) = pause {
+                timer.pausable(
+                    pause_handle,
+                    process_tree,
+                    self.options.exclude_paused_time,
+                    self.strict_errors,
+                );
+            }
+            let Some(result) = handle.wait(&mut timer)? else {
+                timer.take_error()?;
+                if timer.terminate() {
+                    event!("termination requested by tick function");
+                    self.terminate_for_timeout = true;
+                } else {
+                    event!("exceeded time limit");
+                }
+                return Ok(None);
+            };
+            let std_result = process.try_wait()?;
+            ExitStatus::new(result, std_result.expect("missing exit status"))
+        };
+        // Orphans might keep the pipes open.
+        self.reap_orphans()?;
+        status.elapsed = Some(self.start.elapsed());
+        event!(exit_status = %status, "reaped process");
+
+        event!("waiting for pipes to close");
+        let mut buffers = join_fn().transpose()?.unwrap_or_default();
+        let first_byte = |index| {
+            buffers
+                .get(index)
+                .and_then(|x: &ReadBuffer| x.first_read)
+                .map(|x| x.saturating_duration_since(self.start))
+        };
+        self.report.stdout_first_byte = first_byte(0);
+        self.report.stderr_first_byte = first_byte(1);
+        #[cfg(unix)]
+        if buffers.len() > 2 {
+            self.report.extra_pipes =
+                buffers.split_off(2).into_iter().map(|x| x.inner).collect();
+        }
+        Ok(Some(P::new_result(status, buffers)))
+    }
+
+    fn finish(
+        &mut self,
+        mut result: WaitResult<<Self as Control>::Result>,
+    ) -> WaitResult<<Self as Control>::Result> {
+        // If the process exited normally, identifier reuse might cause a
+        // different process to be terminated.
+        if self.terminate_for_timeout && !matches!(result, Ok(Some(_))) {
+            let next_result = self.terminate();
+            if self.strict_errors && result.is_ok() {
+                if let Err(error) = next_result {
+                    result = Err(error);
+                }
+            }
+        }
+        #[attr_alias(procfs)]
+        if result.is_ok() {
+            let next_result = self.check_leftovers();
+            if self.strict_errors {
+                if let Err(error) = next_result {
+                    result = Err(error);
+                }
+            }
+        }
+        result
+    }
+
+    #[attr_alias(procfs)]
+    fn check_leftovers(&mut self) -> io::Result<()> {
+        let Some((leftovers, terminate)) = self.options.leftovers.take()
+        else {
+            return Ok(());
+        };
+        let mut leftovers = leftovers?;
+        let processes = leftovers.find()?;
+        event!(count = processes.len(), "found leftover processes");
+        self.report.leftover_processes = processes;
+        if terminate {
+            leftovers.terminate()?;
+            event!("killed leftover processes");
+        }
+        Ok(())
     }
 }
 
@@ -154,6 +587,29 @@ where
         self
     }
 
+    #[inline]
+    fn soft_time_limit<F>(mut self, limit: Duration, handler: F) -> Self
+    where
+        F: 'static + FnOnce(u32) -> io::Result<()> + Send,
+    {
+        self.options.soft_time_limit =
+            Some(SoftTimeLimit::new(limit, Handler::new(handler)));
+        self
+    }
+
+    #[inline]
+    fn on_tick<F>(mut self, interval: Duration, tick_fn: F) -> Self
+    where
+        F: 'static + FnMut(&Progress) -> Decision + Send,
+    {
+        let tick = Tick::new(interval, tick_fn);
+        let [stdout, stderr] = tick.counters();
+        self.options.stdout.counter = Some(stdout);
+        self.options.stderr.counter = Some(stderr);
+        self.options.tick = Some(tick);
+        self
+    }
+
     #[inline]
     fn strict_errors(mut self) -> Self {
         self.strict_errors = true;
@@ -166,47 +622,385 @@ where
         self
     }
 
+    #[inline]
+    fn before_terminate<F>(mut self, hook: F) -> Self
+    where
+        F: 'static + FnOnce(u32) -> io::Result<()> + Send,
+    {
+        self.options.before_terminate = Some(Handler::new(hook));
+        self
+    }
+
+    #[cfg(any(doc, unix))]
+    #[inline]
+    fn terminate_process_tree(mut self) -> Self {
+        #[cfg(unix)]
+        {
+            self.options.process_tree =
+                Some(imp::ProcessTree::new(self.process.get()));
+        }
+        self
+    }
+
+    #[attr_alias(procfs, cfg(any(doc, *)))]
+    #[inline]
+    fn cgroup<T>(mut self, path: T) -> Self
+    where
+        T: AsRef<Path>,
+    {
+        self.options.cgroup =
+            Some(imp::Cgroup::new(path.as_ref(), self.process.get()));
+        self
+    }
+
+    #[cfg(any(doc, unix))]
+    #[inline]
+    fn forward_signals(
+        mut self,
+        signals: &[c_int],
+        grace_period: Duration,
+    ) -> Self {
+        self.options.forward_signals =
+            Some((signals.to_owned(), grace_period));
+        self
+    }
+
+    #[cfg(any(doc, unix))]
+    #[inline]
+    fn pausable(mut self, handle: &PauseHandle) -> Self {
+        #[cfg(unix)]
+        {
+            self.options.pause_handle = Some(handle.clone());
+        }
+        self
+    }
+
+    #[cfg(any(doc, unix))]
+    #[inline]
+    fn exclude_paused_time(mut self) -> Self {
+        #[cfg(unix)]
+        {
+            self.options.exclude_paused_time = true;
+        }
+        self
+    }
+
+    #[attr_alias(procfs, cfg(any(doc, *)))]
+    #[inline]
+    fn adopt_orphans(mut self) -> Self {
+        self.options.subreaper = Some(imp::Subreaper::new(self.process.get()));
+        self
+    }
+
+    #[attr_alias(procfs, cfg(any(doc, *)))]
+    #[inline]
+    fn leftover_processes(mut self, terminate: bool) -> Self {
+        self.options.leftovers =
+            Some((imp::Leftovers::new(self.process.get()), terminate));
+        self
+    }
+
     #[inline]
     fn stdout_filter<T>(mut self, filter: T) -> Self
     where
-        Self: Control<Result = Output>,
         T: PipeFilter,
     {
         assert!(self.process.get().stdout.is_some(), "stdout is not piped");
 
-        self.options.stdout_filter = Some(filter.into());
+        self.options.stdout.filter = Some(filter.into());
         self
     }
 
     #[inline]
     fn stderr_filter<T>(mut self, filter: T) -> Self
     where
-        Self: Control<Result = Output>,
         T: PipeFilter,
     {
         assert!(self.process.get().stderr.is_some(), "stderr is not piped");
 
-        self.options.stderr_filter = Some(filter.into());
+        self.options.stderr.filter = Some(filter.into());
         self
     }
 
     #[inline]
-    fn wait(mut self) -> WaitResult<Self::Result> {
-        let _ = self.process.get().stdin.take();
-        let mut result = self.process.run_wait(self.options);
+    fn stdout_limit(mut self, limit: usize) -> Self {
+        assert!(self.process.get().stdout.is_some(), "stdout is not piped");
+
+        self.options.stdout.limit = Some(limit);
+        self
+    }
+
+    #[inline]
+    fn stderr_limit(mut self, limit: usize) -> Self {
+        assert!(self.process.get().stderr.is_some(), "stderr is not piped");
+
+        self.options.stderr.limit = Some(limit);
+        self
+    }
 
+    #[cfg(feature = "log")]
+    #[inline]
+    fn log_stdout<T>(
+        mut self,
+        target: &str,
+        level: log::Level,
+        program: T,
+    ) -> Self
+    where
+        T: AsRef<OsStr>,
+    {
         let process = self.process.get();
-        // If the process exited normally, identifier reuse might cause a
-        // different process to be terminated.
-        if self.terminate_for_timeout && !matches!(result, Ok(Some(_))) {
-            let next_result = process.kill().and_then(|()| process.wait());
-            if self.strict_errors && result.is_ok() {
-                if let Err(error) = next_result {
-                    result = Err(error);
-                }
-            }
+        assert!(process.stdout.is_some(), "stdout is not piped");
+
+        self.options.stdout.log = Some(pipe::log_lines(
+            target,
+            level,
+            process.id(),
+            program.as_ref(),
+        ));
+        self
+    }
+
+    #[cfg(feature = "log")]
+    #[inline]
+    fn log_stderr<T>(
+        mut self,
+        target: &str,
+        level: log::Level,
+        program: T,
+    ) -> Self
+    where
+        T: AsRef<OsStr>,
+    {
+        let process = self.process.get();
+        assert!(process.stderr.is_some(), "stderr is not piped");
+
+        self.options.stderr.log = Some(pipe::log_lines(
+            target,
+            level,
+            process.id(),
+            program.as_ref(),
+        ));
+        self
+    }
+
+    #[inline]
+    fn stdout_to<T>(mut self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send,
+    {
+        assert!(self.process.get().stdout.is_some(), "stdout is not piped");
+
+        self.options.stdout.writer = Some(pipe::WriterWrapper::new(writer));
+        self.options.stdout.discard = true;
+        self
+    }
+
+    #[inline]
+    fn stderr_to<T>(mut self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send,
+    {
+        assert!(self.process.get().stderr.is_some(), "stderr is not piped");
+
+        self.options.stderr.writer = Some(pipe::WriterWrapper::new(writer));
+        self.options.stderr.discard = true;
+        self
+    }
+
+    #[inline]
+    fn tee_stdout<T>(mut self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send,
+    {
+        assert!(self.process.get().stdout.is_some(), "stdout is not piped");
+
+        self.options.stdout.writer = Some(pipe::WriterWrapper::new(writer));
+        self.options.stdout.discard = false;
+        self
+    }
+
+    #[inline]
+    fn tee_stderr<T>(mut self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send,
+    {
+        assert!(self.process.get().stderr.is_some(), "stderr is not piped");
+
+        self.options.stderr.writer = Some(pipe::WriterWrapper::new(writer));
+        self.options.stderr.discard = false;
+        self
+    }
+
+    #[inline]
+    fn stdout_capture(mut self, capture: Capture) -> Self
+    where
+        Self: Control<Result = Output>,
+    {
+        assert!(self.process.get().stdout.is_some(), "stdout is not piped");
+
+        self.options.stdout.capture = capture;
+        self
+    }
+
+    #[inline]
+    fn stderr_capture(mut self, capture: Capture) -> Self
+    where
+        Self: Control<Result = Output>,
+    {
+        assert!(self.process.get().stderr.is_some(), "stderr is not piped");
+
+        self.options.stderr.capture = capture;
+        self
+    }
+
+    #[cfg(any(doc, unix))]
+    #[inline]
+    fn extra_pipe<T>(mut self, pipe: ExtraPipe, filter: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: PipeFilter,
+    {
+        let ExtraPipe { reader, writer } = pipe;
+        drop(writer);
+
+        let mut options = pipe::Options::new();
+        options.filter = Some(filter.into());
+        self.options.extra_pipes.push((reader, options));
+        self
+    }
+
+    #[attr_alias(unix_pty, cfg(any(doc, *)))]
+    #[inline]
+    fn pty(mut self, pty: Pty) -> Self
+    where
+        Self: Control<Result = Output>,
+    {
+        let Pty { master, slave } = pty;
+        drop(slave);
+
+        self.process.get().stdout = Some(master.into());
+        self
+    }
+
+    #[cfg(any(doc, unix))]
+    #[inline]
+    fn session(
+        self,
+    ) -> io::Result<impl crate::Session<Result = Self::Result> + Debug> {
+        Session::new(self)
+    }
+
+    #[inline]
+    fn events(mut self) -> io::Result<Events>
+    where
+        Self: 'static + Control<Result = Output> + Send,
+    {
+        let (sender, receiver) = mpsc::sync_channel(0);
+        self.options.stdout.events =
+            Some(pipe::EventSender::new(sender.clone(), Event::Stdout));
+        self.options.stderr.events =
+            Some(pipe::EventSender::new(sender.clone(), Event::Stderr));
+
+        let terminate_for_timeout = self.terminate_for_timeout;
+        let _ = thread::Builder::new().spawn(move || {
+            let event = self.wait().map(|output| match output {
+                Some(output) => Event::Exited(output.status),
+                None if terminate_for_timeout => Event::Terminated,
+                None => Event::TimedOut,
+            });
+            let _ = sender.send(event);
+        })?;
+        Ok(Events(receiver))
+    }
+
+    #[inline]
+    fn wait_scoped<T, U>(
+        mut self,
+        stdout_filter: T,
+        stderr_filter: U,
+    ) -> WaitResult<Self::Result>
+    where
+        Self: Control<Result = Output>,
+        T: FnMut(&[u8]) -> io::Result<bool> + Send,
+        U: FnMut(&[u8]) -> io::Result<bool> + Send,
+    {
+        let span = self.span();
+        if !self.options.extra_pipes.is_empty() {
+            let result = Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "extra pipes cannot be read by scoped waits",
+            ));
+            span.finish(&result);
+            return result;
         }
+        // The readers can only be joined once the process has exited.
+        self.terminate_for_timeout = true;
 
+        let _ = self.process.get().stdin.take();
+        let pipes = self.process.take_pipes(&mut self.options);
+        let result = thread::scope(|scope| {
+            let reader = pipes
+                .map(|mut pipes| {
+                    if let Some(pipe) = &mut pipes[0] {
+                        pipe.prepend_filter(stdout_filter);
+                    }
+                    if let Some(pipe) = &mut pipes[1] {
+                        pipe.prepend_filter(stderr_filter);
+                    }
+                    thread::Builder::new()
+                        .spawn_scoped(scope, move || imp::read_pipes(pipes))
+                })
+                .transpose();
+            let result = reader.and_then(|reader| {
+                self.run_wait(|| {
+                    reader.map(|x| {
+                        x.join().unwrap_or_else(|x| panic::resume_unwind(x))
+                    })
+                })
+            });
+            // The process must be terminated before the reader is joined at
+            // the end of the scope.
+            self.finish(result)
+        });
+        span.finish(&result);
         result
     }
+
+    #[inline]
+    fn wait(self) -> WaitResult<Self::Result> {
+        self.wait_with_report().map(|x| x.result)
+    }
+
+    #[inline]
+    fn wait_with_report(mut self) -> io::Result<Report<Self::Result>> {
+        let span = self.span();
+        let _ = self.process.get().stdin.take();
+        let reader = self
+            .process
+            .take_pipes(&mut self.options)
+            .map(|pipes| {
+                thread::Builder::new().spawn(move || imp::read_pipes(pipes))
+            })
+            .transpose();
+        #[cfg(unix)]
+        let session_reader = self.session_reader.take();
+        let result = reader.and_then(|reader| {
+            self.run_wait(|| {
+                let buffers = reader.map(join_reader);
+                #[cfg(unix)]
+                if let Some(session_reader) = session_reader {
+                    return Some(join_session_reader(session_reader, buffers));
+                }
+                buffers
+            })
+        });
+        let result = self.finish(result);
+        span.finish(&result);
+        result.map(|x| self.report.with_result(x))
+    }
 }
@@ -1,24 +1,51 @@
+#[cfg(feature = "log")]
+use std::ffi::OsStr;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::io;
+use std::io::Write;
 use std::process::ChildStdout;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::SyncSender;
+use std::time::Instant;
 
 use crate::imp;
-use crate::PipeFilter as Filter;
+use crate::Capture;
+use crate::Event;
+use crate::PipeFilter;
 
-pub(super) struct FilterWrapper(Box<dyn Filter>);
+use super::timer::Counter;
 
-impl Debug for FilterWrapper {
+// Equivalent to [`PipeFilter`] but without the lifetime bound.
+pub(super) trait Filter:
+    FnMut(&[u8]) -> io::Result<bool> + Send
+{
+}
+
+impl<T> Filter for T where T: FnMut(&[u8]) -> io::Result<bool> + Send {}
+
+pub(super) struct FilterWrapper<'a>(Box<dyn 'a + Filter>);
+
+impl<'a> FilterWrapper<'a> {
+    pub(super) fn new<T>(filter: T) -> Self
+    where
+        T: 'a + Filter,
+    {
+        Self(Box::new(filter))
+    }
+}
+
+impl Debug for FilterWrapper<'_> {
     #[inline]
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("FilterWrapper").finish_non_exhaustive()
     }
 }
 
-impl<T> From<T> for FilterWrapper
+impl<T> From<T> for FilterWrapper<'_>
 where
-    T: Filter,
+    T: PipeFilter,
 {
     #[inline]
     fn from(value: T) -> Self {
@@ -26,30 +53,244 @@ where
     }
 }
 
-pub(crate) struct Pipe {
-    pub(crate) inner: ChildStdout,
-    filter: FilterWrapper,
+pub(super) struct WriterWrapper(Box<dyn Write + Send>);
+
+impl Debug for WriterWrapper {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriterWrapper").finish_non_exhaustive()
+    }
+}
+
+impl WriterWrapper {
+    pub(super) fn new<T>(writer: T) -> Self
+    where
+        T: 'static + Write + Send,
+    {
+        Self(Box::new(writer))
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct EventSender {
+    sender: SyncSender<io::Result<Event>>,
+    new_fn: fn(Vec<u8>) -> Event,
 }
 
-impl Pipe {
-    pub(super) fn new(
-        pipe: imp::OwnedFd,
-        filter: Option<FilterWrapper>,
+impl EventSender {
+    pub(super) const fn new(
+        sender: SyncSender<io::Result<Event>>,
+        new_fn: fn(Vec<u8>) -> Event,
     ) -> Self {
+        Self { sender, new_fn }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Options<'a> {
+    pub(super) filter: Option<FilterWrapper<'a>>,
+    pub(super) writer: Option<WriterWrapper>,
+    pub(super) discard: bool,
+    pub(super) capture: Capture,
+    pub(super) limit: Option<usize>,
+    pub(super) events: Option<EventSender>,
+    pub(super) counter: Option<Counter>,
+    #[cfg(feature = "log")]
+    pub(super) log: Option<FilterWrapper<'a>>,
+}
+
+impl Options<'_> {
+    pub(super) const fn new() -> Self {
+        Self {
+            filter: None,
+            writer: None,
+            discard: false,
+            capture: Capture::All,
+            limit: None,
+            events: None,
+            counter: None,
+            #[cfg(feature = "log")]
+            log: None,
+        }
+    }
+
+    pub(super) fn is_used(&self) -> bool {
+        #[cfg(feature = "log")]
+        if self.log.is_some() {
+            return true;
+        }
+        self.filter.is_some()
+    }
+}
+
+#[cfg(feature = "log")]
+pub(super) fn log_lines(
+    target: &str,
+    level: log::Level,
+    pid: u32,
+    program: &OsStr,
+) -> FilterWrapper<'static> {
+    let target = target.to_owned();
+    let program = program.to_string_lossy().into_owned();
+    crate::filters::lines(move |line| {
+        log::log!(
+            target: &target,
+            level,
+            pid,
+            program = program.as_str();
+            "{}",
+            String::from_utf8_lossy(line),
+        );
+        Ok(())
+    })
+    .into()
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ReadBuffer {
+    pub(crate) inner: Vec<u8>,
+    pub(super) first_read: Option<Instant>,
+}
+
+impl ReadBuffer {
+    #[cfg(windows)]
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+            first_read: None,
+        }
+    }
+}
+
+pub(crate) struct Pipe<'a> {
+    pub(crate) inner: ChildStdout,
+    options: Options<'a>,
+    length: u64,
+}
+
+impl<'a> Pipe<'a> {
+    // Omitted output is only removed after this many extra bytes have been
+    // read, to avoid moving the tail for every read.
+    const MIN_COMPACT_LENGTH: usize = 4096;
+
+    pub(super) fn new(pipe: imp::OwnedFd, options: Options<'a>) -> Self {
         Self {
             inner: pipe.into(),
-            filter: filter.unwrap_or_else(|| (|_: &_| Ok(true)).into()),
+            options,
+            length: 0,
+        }
+    }
+
+    pub(super) fn prepend_filter<T>(&mut self, mut filter: T)
+    where
+        T: 'a + Filter,
+    {
+        let mut next_filter = self.options.filter.take();
+        self.options.filter =
+            Some(FilterWrapper::new(move |buffer: &[_]| {
+                Ok(filter(buffer)?
+                    && next_filter
+                        .as_mut()
+                        .map_or(Ok(true), |next_filter| {
+                            (next_filter.0)(buffer)
+                        })?)
+            }));
+    }
+
+    const fn limits(&self) -> Option<(usize, usize)> {
+        match self.options.capture {
+            Capture::All => None,
+            Capture::Tail(tail) => Some((0, tail)),
+            Capture::HeadTail(head, tail) => Some((head, tail)),
+        }
+    }
+
+    // Returns the number of bytes that should be included in the output.
+    fn accept(&mut self, mut buffer: &[u8]) -> io::Result<usize> {
+        if let Some(limit) = &mut self.options.limit {
+            if *limit == 0 {
+                return Ok(0);
+            }
+            let length = buffer.len().min(*limit);
+            *limit -= length;
+            buffer = &buffer[..length];
+        }
+        #[cfg(feature = "log")]
+        if let Some(log) = &mut self.options.log {
+            let _ = (log.0)(buffer)?;
+        }
+        if let Some(filter) = &mut self.options.filter {
+            if !(filter.0)(buffer)? {
+                return Ok(0);
+            }
+        }
+        if let Some(writer) = &mut self.options.writer {
+            writer.0.write_all(buffer)?;
         }
+        if let Some(events) = &self.options.events {
+            // Output is discarded if the receiver was dropped, so that the
+            // process can continue to run.
+            let _ = events.sender.send(Ok((events.new_fn)(buffer.to_owned())));
+            return Ok(0);
+        }
+        Ok(if self.options.discard {
+            0
+        } else {
+            buffer.len()
+        })
     }
 
     pub(crate) fn run_filter(
         &mut self,
-        buffer: &mut Vec<u8>,
+        buffer: &mut ReadBuffer,
         index: usize,
     ) -> io::Result<()> {
+        let _ = buffer.first_read.get_or_insert_with(Instant::now);
+        let buffer = &mut buffer.inner;
         debug_assert_ne!(index, buffer.len());
-        if !(self.filter.0)(&buffer[index..])? {
-            buffer.truncate(index);
+        if let Some(counter) = &self.options.counter {
+            let _ = counter.fetch_add(buffer.len() - index, Ordering::Relaxed);
+        }
+        let length = self.accept(&buffer[index..])?;
+        buffer.truncate(index + length);
+        if length == 0 {
+            return Ok(());
+        }
+
+        self.length += (buffer.len() - index) as u64;
+        if let Some((head, tail)) = self.limits() {
+            let end = buffer.len().saturating_sub(tail);
+            if end >= head + tail.max(Self::MIN_COMPACT_LENGTH) {
+                let _ = buffer.drain(head..end);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(
+        &mut self,
+        buffer: &mut ReadBuffer,
+    ) -> io::Result<()> {
+        let buffer = &mut buffer.inner;
+        if let Some(writer) = &mut self.options.writer {
+            writer.0.flush()?;
+        }
+
+        if let Some((head, tail)) = self.limits() {
+            let end = buffer.len().saturating_sub(tail);
+            if end > head {
+                let _ = buffer.drain(head..end);
+            }
+            if let Capture::HeadTail(..) = self.options.capture {
+                let omitted = self.length - buffer.len() as u64;
+                if omitted != 0 {
+                    let marker = format!(
+                        "\n[{} of {} bytes omitted]\n",
+                        omitted, self.length,
+                    );
+                    let _ = buffer.splice(head..head, marker.into_bytes());
+                }
+            }
         }
         Ok(())
     }
@@ -0,0 +1,221 @@
+use std::io;
+use std::io::Write;
+use std::mem;
+use std::process::ChildStdin;
+use std::process::ChildStdout;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::imp;
+use crate::Control;
+use crate::WaitResult;
+
+use super::pipe;
+use super::Buffer;
+use super::Process;
+
+#[derive(Debug)]
+pub(crate) struct Session<P>
+where
+    P: Process,
+{
+    buffer: Buffer<P>,
+    stdin: Option<ChildStdin>,
+    stdout: ChildStdout,
+    output: Vec<u8>,
+    eof: bool,
+    deadline: Option<Instant>,
+    expired: bool,
+}
+
+#[attr_alias::eval]
+impl<P> Session<P>
+where
+    P: Process,
+{
+    pub(super) fn new(mut buffer: Buffer<P>) -> io::Result<Self> {
+        let process = buffer.process.get();
+        let stdout = process.stdout.take().expect("stdout is not piped");
+        imp::set_nonblocking(&stdout)?;
+        let stdin = process.stdin.take();
+        if let Some(stdin) = &stdin {
+            imp::set_nonblocking(stdin)?;
+        }
+
+        // The memory limit cannot wait until the process is waited on.
+        #[attr_alias(memory_limit)]
+        if let Some(memory_limit) = buffer.options.memory_limit.take() {
+            imp::Process::new(process).set_memory_limit(memory_limit)?;
+        }
+
+        // Other pipes must be read during the session, since the process
+        // could otherwise block writing to them.
+        let stdout_options =
+            mem::replace(&mut buffer.options.stdout, pipe::Options::new());
+        let pipes = buffer.process.take_pipes(&mut buffer.options);
+        buffer.options.stdout = stdout_options;
+        buffer.session_reader = pipes
+            .map(|pipes| {
+                thread::Builder::new().spawn(move || imp::read_pipes(pipes))
+            })
+            .transpose()?;
+
+        let deadline = buffer
+            .options
+            .time_limit
+            .and_then(|x| Instant::now().checked_add(x));
+        Ok(Self {
+            buffer,
+            stdin,
+            stdout,
+            output: Vec::new(),
+            eof: false,
+            deadline,
+            expired: false,
+        })
+    }
+
+    fn next_timeout(
+        &mut self,
+        step_deadline: Option<Instant>,
+    ) -> io::Result<Option<Option<Duration>>> {
+        let now = Instant::now();
+        if self.deadline.is_some_and(|x| x <= now) {
+            self.expired = true;
+            if self.buffer.terminate_for_timeout {
+                let result = self.buffer.terminate();
+                if self.buffer.strict_errors {
+                    result?;
+                }
+            }
+            return Ok(None);
+        }
+
+        let deadline = match (self.deadline, step_deadline) {
+            (Some(deadline), Some(step_deadline)) => {
+                Some(deadline.min(step_deadline))
+            }
+            (deadline, step_deadline) => deadline.or(step_deadline),
+        };
+        Ok(match deadline {
+            Some(deadline) if deadline <= now => None,
+            deadline => Some(deadline.map(|x| x - now)),
+        })
+    }
+}
+
+impl<P> crate::Session for Session<P>
+where
+    P: Process,
+{
+    type Result = P::Result;
+
+    #[inline]
+    fn send(&mut self, mut input: &[u8]) -> WaitResult<()> {
+        if self.expired {
+            return Ok(None);
+        }
+
+        while !input.is_empty() {
+            let stdin = self.stdin.as_mut().expect("stdin is not piped");
+            match stdin.write(input) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(length) => input = &input[length..],
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    let Some(timeout) = self.next_timeout(None)? else {
+                        return Ok(None);
+                    };
+                    let stdin = self.stdin.as_ref().expect("missing stdin");
+                    let _ = imp::poll_write(stdin, timeout)?;
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Some(()))
+    }
+
+    #[inline]
+    fn expect(
+        &mut self,
+        pattern: &[u8],
+        timeout: Duration,
+    ) -> WaitResult<Vec<u8>> {
+        self.expect_fn(
+            |output| {
+                if pattern.is_empty() {
+                    return Some(0);
+                }
+                output
+                    .windows(pattern.len())
+                    .position(|x| x == pattern)
+                    .map(|x| x + pattern.len())
+            },
+            timeout,
+        )
+    }
+
+    #[inline]
+    fn expect_fn<F>(
+        &mut self,
+        mut matcher: F,
+        timeout: Duration,
+    ) -> WaitResult<Vec<u8>>
+    where
+        F: FnMut(&[u8]) -> Option<usize>,
+    {
+        if self.expired {
+            return Ok(None);
+        }
+
+        let step_deadline = Instant::now().checked_add(timeout);
+        loop {
+            if let Some(length) = matcher(&self.output) {
+                return Ok(Some(self.output.drain(..length).collect()));
+            }
+            if self.eof {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Process closed stdout",
+                ));
+            }
+
+            let Some(timeout) = self.next_timeout(step_deadline)? else {
+                return Ok(None);
+            };
+            if imp::poll_read(&self.stdout, timeout)? {
+                self.eof = !imp::read_nonblocking(
+                    &mut self.stdout,
+                    &mut self.output,
+                )?;
+            }
+        }
+    }
+
+    #[inline]
+    fn wait(self) -> WaitResult<Self::Result> {
+        let Self {
+            mut buffer,
+            stdin,
+            stdout,
+            output,
+            deadline,
+            expired,
+            ..
+        } = self;
+        if expired {
+            return Ok(None);
+        }
+
+        // The process might be waiting for stdin to be closed.
+        drop(stdin);
+
+        buffer.process.get().stdout = Some(stdout);
+        buffer.options.time_limit =
+            deadline.map(|x| x.saturating_duration_since(Instant::now()));
+        buffer
+            .wait()
+            .map(|x| x.map(|result| P::prepend_stdout(result, output)))
+    }
+}
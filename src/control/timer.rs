@@ -0,0 +1,320 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::io;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::Decision;
+use crate::Progress;
+
+#[cfg(unix)]
+use crate::imp::ProcessTree;
+#[cfg(unix)]
+use crate::imp::SignalForwarder;
+#[cfg(unix)]
+use crate::PauseHandle;
+
+use super::trace::event;
+use super::Handler;
+
+pub(super) type Counter = Arc<AtomicUsize>;
+
+pub(super) struct Tick {
+    interval: Duration,
+    tick_fn: Box<dyn FnMut(&Progress) -> Decision + Send>,
+    stdout_bytes: Counter,
+    stderr_bytes: Counter,
+}
+
+impl Tick {
+    pub(super) fn new<F>(interval: Duration, tick_fn: F) -> Self
+    where
+        F: 'static + FnMut(&Progress) -> Decision + Send,
+    {
+        assert_ne!(Duration::ZERO, interval, "tick interval is zero");
+
+        Self {
+            interval,
+            tick_fn: Box::new(tick_fn),
+            stdout_bytes: Counter::default(),
+            stderr_bytes: Counter::default(),
+        }
+    }
+
+    pub(super) fn counters(&self) -> [Counter; 2] {
+        [
+            Arc::clone(&self.stdout_bytes),
+            Arc::clone(&self.stderr_bytes),
+        ]
+    }
+}
+
+impl Debug for Tick {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tick")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct SoftTimeLimit {
+    limit: Duration,
+    handler: Handler,
+}
+
+impl SoftTimeLimit {
+    pub(super) const fn new(limit: Duration, handler: Handler) -> Self {
+        Self { limit, handler }
+    }
+}
+
+#[cfg(unix)]
+struct Pause {
+    handle: PauseHandle,
+    process_tree: ProcessTree,
+    exclude_time: bool,
+    strict_errors: bool,
+    paused_at: Option<Instant>,
+}
+
+#[cfg(unix)]
+impl Drop for Pause {
+    fn drop(&mut self) {
+        self.handle.remove_waiter();
+    }
+}
+
+// Yields the durations that the process should be waited for between checks.
+// An item of [None] waits indefinitely, and the iterator ends once the process
+// should no longer be waited for.
+pub(super) struct Timer {
+    start: Instant,
+    pid: u32,
+    deadline: Option<Instant>,
+    soft_limit: Option<(SoftTimeLimit, Instant)>,
+    tick: Option<(Tick, Instant)>,
+    #[cfg(unix)]
+    forwarder: Option<(SignalForwarder, Duration)>,
+    #[cfg(unix)]
+    pause: Option<Pause>,
+    terminate: bool,
+    error: Option<io::Error>,
+}
+
+impl Timer {
+    // Signals are only forwarded when the process is not being waited on, so
+    // this interval limits the delay.
+    #[cfg(unix)]
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    pub(super) fn new(
+        start: Instant,
+        pid: u32,
+        time_limit: Option<Duration>,
+        soft_limit: Option<SoftTimeLimit>,
+        tick: Option<Tick>,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            start,
+            pid,
+            deadline: time_limit.and_then(|x| now.checked_add(x)),
+            soft_limit: soft_limit.and_then(|x| {
+                now.checked_add(x.limit).map(|deadline| (x, deadline))
+            }),
+            tick: tick.and_then(|x| {
+                now.checked_add(x.interval).map(|next| (x, next))
+            }),
+            #[cfg(unix)]
+            forwarder: None,
+            #[cfg(unix)]
+            pause: None,
+            terminate: false,
+            error: None,
+        }
+    }
+
+    #[cfg(unix)]
+    pub(super) fn forward_signals(
+        &mut self,
+        forwarder: SignalForwarder,
+        grace_period: Duration,
+    ) {
+        self.forwarder = Some((forwarder, grace_period));
+    }
+
+    // This method must be called on the thread that will use this instance,
+    // so that it can be woken for requests.
+    #[cfg(unix)]
+    pub(super) fn pausable(
+        &mut self,
+        handle: PauseHandle,
+        process_tree: ProcessTree,
+        exclude_time: bool,
+        strict_errors: bool,
+    ) {
+        handle.add_waiter();
+        self.pause = Some(Pause {
+            handle,
+            process_tree,
+            exclude_time,
+            strict_errors,
+            paused_at: None,
+        });
+    }
+
+    pub(super) const fn terminate(&self) -> bool {
+        self.terminate
+    }
+
+    pub(super) fn take_error(&mut self) -> io::Result<()> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    fn run_soft_limit(&mut self, now: Instant) {
+        if self.soft_limit.as_ref().is_some_and(|&(_, x)| x <= now) {
+            let (soft_limit, _) =
+                self.soft_limit.take().expect("missing soft time limit");
+            event!("exceeded soft time limit");
+            if let Err(error) = soft_limit.handler.call(self.pid) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn run_forwarder(&mut self, now: Instant) {
+        let Some((forwarder, grace_period)) = &mut self.forwarder else {
+            return;
+        };
+        match forwarder.forward(self.pid) {
+            Ok(false) => {}
+            Ok(true) => {
+                event!("forwarded signals");
+                if let Some(deadline) = now.checked_add(*grace_period) {
+                    self.deadline = Some(
+                        self.deadline.map_or(deadline, |x| x.min(deadline)),
+                    );
+                }
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    #[cfg(unix)]
+    fn run_pause(&mut self, now: Instant) {
+        let Some(pause) = &mut self.pause else {
+            return;
+        };
+        if let Some(paused_at) = &mut pause.paused_at {
+            if pause.exclude_time {
+                let paused = now.saturating_duration_since(*paused_at);
+                self.deadline =
+                    self.deadline.and_then(|x| x.checked_add(paused));
+            }
+            *paused_at = now;
+        }
+
+        let paused = pause.handle.is_paused();
+        if paused == pause.paused_at.is_some() {
+            return;
+        }
+        // Errors are handled like those terminating the process, since the
+        // result is usually more important.
+        match pause.process_tree.suspend(paused) {
+            Ok(()) => {}
+            Err(error) if pause.strict_errors => {
+                self.error = Some(error);
+                return;
+            }
+            Err(_error) => {
+                event!(error = %_error, "failed to pause or resume process");
+                return;
+            }
+        }
+        if paused {
+            event!("paused process");
+            pause.paused_at = Some(now);
+        } else {
+            event!("resumed process");
+            pause.paused_at = None;
+        }
+    }
+
+    fn run_tick(&mut self, now: Instant) {
+        let Some((tick, next)) = &mut self.tick else {
+            return;
+        };
+        if *next > now {
+            return;
+        }
+        // The function will not be called again if the next tick cannot be
+        // represented.
+        let next_tick = now.checked_add(tick.interval);
+        if let Some(next_tick) = next_tick {
+            *next = next_tick;
+        }
+
+        let progress = Progress {
+            elapsed: now.saturating_duration_since(self.start),
+            stdout_bytes: tick.stdout_bytes.load(Ordering::Relaxed),
+            stderr_bytes: tick.stderr_bytes.load(Ordering::Relaxed),
+        };
+        match (tick.tick_fn)(&progress) {
+            Decision::Continue => {}
+            Decision::ExtendTimeLimit(time_limit) => {
+                self.deadline =
+                    self.deadline.and_then(|x| x.checked_add(time_limit));
+            }
+            Decision::Terminate => self.terminate = true,
+        }
+        if next_tick.is_none() {
+            self.tick = None;
+        }
+    }
+}
+
+impl Iterator for Timer {
+    type Item = Option<Duration>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = Instant::now();
+        if !self.terminate && self.error.is_none() {
+            #[cfg(unix)]
+            self.run_forwarder(now);
+            #[cfg(unix)]
+            self.run_pause(now);
+            self.run_soft_limit(now);
+            self.run_tick(now);
+        }
+        if self.terminate
+            || self.error.is_some()
+            || self.deadline.is_some_and(|x| x <= now)
+        {
+            return None;
+        }
+
+        let next = [
+            self.deadline,
+            self.soft_limit.as_ref().map(|&(_, x)| x),
+            self.tick.as_ref().map(|&(_, x)| x),
+            #[cfg(unix)]
+            self.forwarder.is_some().then(|| now + Self::POLL_INTERVAL),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let next = next.map(|x| x.saturating_duration_since(now));
+        // Pause requests wake the current thread, which is only possible if
+        // the process is waited on by another.
+        #[cfg(unix)]
+        let next = next.or_else(|| self.pause.as_ref().map(|_| Duration::MAX));
+        Some(next)
+    }
+}
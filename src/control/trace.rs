@@ -0,0 +1,89 @@
+use std::time::Duration;
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+#[cfg(feature = "tracing")]
+use tracing::field;
+#[cfg(feature = "tracing")]
+use tracing::span::EnteredSpan;
+
+use crate::ExitStatus;
+use crate::WaitResult;
+
+#[cfg(feature = "tracing")]
+macro_rules! event {
+    ( $($token:tt)* ) => {
+        ::tracing::debug!($($token)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! event {
+    ( $($token:tt)* ) => {};
+}
+pub(super) use event;
+
+#[cfg(feature = "tracing")]
+pub(super) struct Span {
+    inner: EnteredSpan,
+    start: Instant,
+    terminate_for_timeout: bool,
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(super) struct Span;
+
+impl Span {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(super) fn new(
+        pid: u32,
+        time_limit: Option<Duration>,
+        memory_limit: Option<usize>,
+        terminate_for_timeout: bool,
+    ) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self {
+                inner: tracing::info_span!(
+                    "wait",
+                    pid,
+                    time_limit = ?time_limit,
+                    memory_limit = ?memory_limit,
+                    elapsed = field::Empty,
+                    outcome = field::Empty,
+                    exit_status = field::Empty,
+                )
+                .entered(),
+                start: Instant::now(),
+                terminate_for_timeout,
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        Self
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(super) fn finish<T>(self, result: &WaitResult<T>)
+    where
+        T: AsRef<ExitStatus>,
+    {
+        #[cfg(feature = "tracing")]
+        {
+            let span = &self.inner;
+            let _ = span.record("elapsed", field::debug(self.start.elapsed()));
+            let outcome = match result {
+                Ok(Some(result)) => {
+                    let _ = span.record(
+                        "exit_status",
+                        field::display(result.as_ref()),
+                    );
+                    "exited"
+                }
+                Ok(None) if self.terminate_for_timeout => "terminated",
+                Ok(None) => "timed out",
+                Err(_) => "error",
+            };
+            let _ = span.record("outcome", outcome);
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Adapters for creating instances of [`PipeFilter`].
+//!
+//! Each function returns a filter that can be passed to
+//! [`Control::stdout_filter`] or [`Control::stderr_filter`].
+//!
+//! [`Control::stderr_filter`]: super::Control::stderr_filter
+//! [`Control::stdout_filter`]: super::Control::stdout_filter
+
+use std::io;
+use std::io::Write;
+use std::mem;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::PipeFilter;
+
+/// Creates a filter that also receives the time each read occurred.
+///
+/// The time is measured from `start`, which will usually be an instance
+/// created immediately before the process was spawned.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io;
+/// use std::process::Command;
+/// use std::process::Stdio;
+/// use std::sync::Arc;
+/// use std::sync::Mutex;
+/// use std::time::Duration;
+/// use std::time::Instant;
+///
+/// use process_control::filters;
+/// use process_control::ChildExt;
+/// use process_control::Control;
+///
+/// let chunks = Arc::new(Mutex::new(Vec::new()));
+/// let start = Instant::now();
+/// let output = Command::new("echo")
+///     .arg("foobar")
+///     .stdout(Stdio::piped())
+///     .spawn()?
+///     .controlled_with_output()
+///     .time_limit(Duration::from_secs(1))
+///     .terminate_for_timeout()
+///     .stdout_filter(filters::timestamped(start, {
+///         let chunks = Arc::clone(&chunks);
+///         move |time, x| {
+///             chunks.lock().unwrap().push((time, x.len()));
+///             Ok(true)
+///         }
+///     }))
+///     .wait()?
+///     .expect("process timed out");
+/// assert!(output.status.success());
+///
+/// let chunks = chunks.lock().unwrap();
+/// assert!(!chunks.is_empty());
+/// assert!(chunks.is_sorted_by_key(|&(time, _)| time));
+/// #
+/// # Ok::<_, io::Error>(())
+/// ```
+#[inline]
+pub fn timestamped<F>(start: Instant, mut filter: F) -> impl PipeFilter
+where
+    F: 'static + FnMut(Duration, &[u8]) -> io::Result<bool> + Send,
+{
+    move |buffer: &[u8]| filter(start.elapsed(), buffer)
+}
+
+/// Creates a filter that writes all bytes to a writer.
+///
+/// Unlike [`Control::tee_stdout`], the writer will not be flushed when the
+/// pipe is closed. All bytes will be accepted.
+///
+/// [`Control::tee_stdout`]: super::Control::tee_stdout
+#[inline]
+pub fn tee<W>(mut writer: W) -> impl PipeFilter
+where
+    W: 'static + Write + Send,
+{
+    move |buffer: &[u8]| writer.write_all(buffer).map(|()| true)
+}
+
+struct Lines<F>
+where
+    F: FnMut(&[u8]) -> io::Result<()>,
+{
+    buffer: Vec<u8>,
+    line_fn: F,
+}
+
+impl<F> Lines<F>
+where
+    F: FnMut(&[u8]) -> io::Result<()>,
+{
+    fn call(&mut self, mut line: &[u8]) -> io::Result<()> {
+        if let Some(next_line) = line.strip_suffix(b"\n") {
+            line = next_line.strip_suffix(b"\r").unwrap_or(next_line);
+        }
+        (self.line_fn)(line)
+    }
+
+    fn push(&mut self, buffer: &[u8]) -> io::Result<()> {
+        for line in buffer.split_inclusive(|&x| x == b'\n') {
+            if !line.ends_with(b"\n") {
+                // Only the last line can be incomplete.
+                self.buffer.extend_from_slice(line);
+            } else if self.buffer.is_empty() {
+                self.call(line)?;
+            } else {
+                self.buffer.extend_from_slice(line);
+                let buffer = mem::take(&mut self.buffer);
+                self.call(&buffer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F> Drop for Lines<F>
+where
+    F: FnMut(&[u8]) -> io::Result<()>,
+{
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            let buffer = mem::take(&mut self.buffer);
+            let _ = self.call(&buffer);
+        }
+    }
+}
+
+/// Creates a filter that calls a function for each line.
+///
+/// Each line will be passed without its terminating `"\n"` or `"\r\n"`, as
+/// [`BufRead::lines`] would return it. Incomplete lines will be buffered
+/// until they are completed. A final line without a terminator will be passed
+/// when the filter is dropped after the pipe is closed, but any error
+/// returned for it will be ignored. All bytes will be accepted.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io;
+/// use std::process::Command;
+/// use std::process::Stdio;
+/// use std::time::Duration;
+///
+/// use process_control::filters;
+/// use process_control::ChildExt;
+/// use process_control::Control;
+///
+/// let exit_status = Command::new("echo")
+///     .arg("foo\nbar")
+///     .stdout(Stdio::piped())
+///     .spawn()?
+///     .controlled()
+///     .time_limit(Duration::from_secs(1))
+///     .terminate_for_timeout()
+///     .stdout_filter(filters::lines(|line| {
+///         println!("process: {}", String::from_utf8_lossy(line));
+///         Ok(())
+///     }))
+///     .wait()?
+///     .expect("process timed out");
+/// assert!(exit_status.success());
+/// #
+/// # Ok::<_, io::Error>(())
+/// ```
+///
+/// [`BufRead::lines`]: ::std::io::BufRead::lines
+#[inline]
+pub fn lines<F>(line_fn: F) -> impl PipeFilter
+where
+    F: 'static + FnMut(&[u8]) -> io::Result<()> + Send,
+{
+    let mut lines = Lines {
+        buffer: Vec::new(),
+        line_fn,
+    };
+    move |buffer: &[u8]| lines.push(buffer).map(|()| true)
+}
+
+/// Creates a filter that passes at most the given number of bytes to another
+/// filter.
+///
+/// The read that exceeds the limit will be truncated before being passed, but
+/// the result of the filter will apply to the whole read. All subsequent
+/// reads will be rejected. To limit the bytes included in [`Output`], use
+/// [`Control::stdout_limit`] or [`Control::stderr_limit`] instead.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io;
+/// use std::process::Command;
+/// use std::process::Stdio;
+/// use std::sync::Arc;
+/// use std::sync::Mutex;
+/// use std::time::Duration;
+///
+/// use process_control::filters;
+/// use process_control::ChildExt;
+/// use process_control::Control;
+///
+/// let head = Arc::new(Mutex::new(Vec::new()));
+/// let exit_status = Command::new("echo")
+///     .arg("foobar")
+///     .stdout(Stdio::piped())
+///     .spawn()?
+///     .controlled()
+///     .time_limit(Duration::from_secs(1))
+///     .terminate_for_timeout()
+///     .stdout_filter(filters::limit(3, {
+///         let head = Arc::clone(&head);
+///         move |x| {
+///             head.lock().unwrap().extend(x);
+///             Ok(true)
+///         }
+///     }))
+///     .wait()?
+///     .expect("process timed out");
+/// assert!(exit_status.success());
+/// assert_eq!(b"foo", &**head.lock().unwrap());
+/// #
+/// # Ok::<_, io::Error>(())
+/// ```
+///
+/// [`Control::stderr_limit`]: super::Control::stderr_limit
+/// [`Control::stdout_limit`]: super::Control::stdout_limit
+/// [`Output`]: super::Output
+#[inline]
+pub fn limit<F>(mut limit: usize, mut filter: F) -> impl PipeFilter
+where
+    F: PipeFilter,
+{
+    move |buffer: &[u8]| {
+        if limit == 0 {
+            return Ok(false);
+        }
+        let length = buffer.len().min(limit);
+        limit -= length;
+        filter(&buffer[..length])
+    }
+}
+
+/// Creates a filter that adds the number of bytes read to a counter.
+///
+/// The counter can be read while the process is running. All bytes will be
+/// accepted.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io;
+/// use std::process::Command;
+/// use std::process::Stdio;
+/// use std::sync::atomic::AtomicU64;
+/// use std::sync::atomic::Ordering;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// use process_control::filters;
+/// use process_control::ChildExt;
+/// use process_control::Control;
+///
+/// let counter = Arc::new(AtomicU64::new(0));
+/// let exit_status = Command::new("echo")
+///     .arg("foobar")
+///     .stdout(Stdio::piped())
+///     .spawn()?
+///     .controlled()
+///     .time_limit(Duration::from_secs(1))
+///     .terminate_for_timeout()
+///     .stdout_filter(filters::count(Arc::clone(&counter)))
+///     .wait()?
+///     .expect("process timed out");
+/// assert!(exit_status.success());
+/// assert_eq!(7, counter.load(Ordering::Relaxed));
+/// #
+/// # Ok::<_, io::Error>(())
+/// ```
+#[inline]
+pub fn count(counter: Arc<AtomicU64>) -> impl PipeFilter {
+    move |buffer: &[u8]| {
+        let _ = counter.fetch_add(buffer.len() as u64, Ordering::Relaxed);
+        Ok(true)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum AnsiState {
+    Text,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+}
+
+/// Creates a filter that passes bytes to another filter with ANSI escape
+/// sequences removed.
+///
+/// Control sequences (`"\x1B["`), operating system commands (`"\x1B]"`) and
+/// other escape sequences are removed, even when they are split across
+/// reads. The result of the filter will apply to the bytes as they were read,
+/// including escape sequences.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io;
+/// use std::process::Command;
+/// use std::process::Stdio;
+/// use std::sync::Arc;
+/// use std::sync::Mutex;
+/// use std::time::Duration;
+///
+/// use process_control::filters;
+/// use process_control::ChildExt;
+/// use process_control::Control;
+///
+/// let text = Arc::new(Mutex::new(Vec::new()));
+/// let exit_status = Command::new("printf")
+///     .arg(r"\033[1mfoo\033[0mbar")
+///     .stdout(Stdio::piped())
+///     .spawn()?
+///     .controlled()
+///     .time_limit(Duration::from_secs(1))
+///     .terminate_for_timeout()
+///     .stdout_filter(filters::strip_ansi({
+///         let text = Arc::clone(&text);
+///         move |x| {
+///             text.lock().unwrap().extend(x);
+///             Ok(true)
+///         }
+///     }))
+///     .wait()?
+///     .expect("process timed out");
+/// assert!(exit_status.success());
+/// assert_eq!(b"foobar", &**text.lock().unwrap());
+/// #
+/// # Ok::<_, io::Error>(())
+/// ```
+#[inline]
+pub fn strip_ansi<F>(mut filter: F) -> impl PipeFilter
+where
+    F: PipeFilter,
+{
+    let mut state = AnsiState::Text;
+    let mut text = Vec::new();
+    move |buffer: &[u8]| {
+        text.clear();
+        for &byte in buffer {
+            state = match (state, byte) {
+                (AnsiState::Text, b'\x1B') => AnsiState::Escape,
+                (AnsiState::Text, _) => {
+                    text.push(byte);
+                    AnsiState::Text
+                }
+                (AnsiState::Escape, b'[') => AnsiState::Csi,
+                (AnsiState::Escape, b']') => AnsiState::Osc,
+                // Intermediate bytes precede the final byte.
+                (AnsiState::Escape, 0x20..=0x2F) => AnsiState::Escape,
+                (AnsiState::Csi, 0x40..=0x7E)
+                | (AnsiState::Escape | AnsiState::OscEscape, _)
+                | (AnsiState::Osc, b'\x07') => AnsiState::Text,
+                (AnsiState::Csi, _) => AnsiState::Csi,
+                (AnsiState::Osc, b'\x1B') => AnsiState::OscEscape,
+                (AnsiState::Osc, _) => AnsiState::Osc,
+            };
+        }
+        filter(&text)
+    }
+}
+
+/// Creates a filter that calls two filters in sequence.
+///
+/// Bytes will only be passed to the second filter if the first accepts them.
+/// They will only be accepted if both filters accept them.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io;
+/// use std::process::Command;
+/// use std::process::Stdio;
+/// use std::time::Duration;
+///
+/// use process_control::filters;
+/// use process_control::ChildExt;
+/// use process_control::Control;
+///
+/// let output = Command::new("echo")
+///     .arg("foobar")
+///     .stdout(Stdio::piped())
+///     .spawn()?
+///     .controlled_with_output()
+///     .time_limit(Duration::from_secs(1))
+///     .terminate_for_timeout()
+///     .stdout_filter(filters::chain(
+///         filters::tee(io::stderr()),
+///         filters::limit(3, |_| Ok(true)),
+///     ))
+///     .wait()?
+///     .expect("process timed out");
+/// assert!(output.status.success());
+/// #
+/// # Ok::<_, io::Error>(())
+/// ```
+#[inline]
+pub fn chain<F, G>(mut first: F, mut second: G) -> impl PipeFilter
+where
+    F: PipeFilter,
+    G: PipeFilter,
+{
+    move |buffer: &[u8]| Ok(first(buffer)? && second(buffer)?)
+}
+
+/// Creates a filter that sends a copy of all bytes to a channel.
+///
+/// If the receiver has been dropped, an error with kind
+/// [`io::ErrorKind::BrokenPipe`] will be returned. All bytes will be
+/// accepted.
+#[inline]
+pub fn to_channel(sender: Sender<Vec<u8>>) -> impl PipeFilter {
+    move |buffer: &[u8]| {
+        sender
+            .send(buffer.to_owned())
+            .map(|()| true)
+            .map_err(|_| io::ErrorKind::BrokenPipe.into())
+    }
+}
@@ -24,11 +24,19 @@
 //!
 //! ### Optional Features
 //!
+//! - **log** -
+//!   Provides [`Control::log_stdout`] and [`Control::log_stderr`] for logging
+//!   output using crate [log].
+//!
 //! - **parking\_lot** -
 //!   Changes the implementation to use crate [parking\_lot] on targets missing
 //!   some syscalls. This feature will reduce the likelihood of resource
 //!   starvation for those targets.
 //!
+//! - **tracing** -
+//!   Emits a span for each call to [`Control::wait`] using crate [tracing],
+//!   with events for each step of waiting on the process.
+//!
 //! # Implementation
 //!
 //! All traits are [sealed], meaning that they can only be implemented by this
@@ -75,9 +83,11 @@
 //! # Ok::<_, io::Error>(())
 //! ```
 //!
+//! [log]: https://crates.io/crates/log
 //! [parking\_lot]: https://crates.io/crates/parking_lot
 //! [`Receiver::recv_timeout`]: ::std::sync::mpsc::Receiver::recv_timeout
 //! [sealed]: https://rust-lang.github.io/api-guidelines/future-proofing.html#c-sealed
+//! [tracing]: https://crates.io/crates/tracing
 //! [wait-timeout]: https://crates.io/crates/wait-timeout
 
 // Only require a nightly compiler when building documentation for docs.rs.
@@ -86,20 +96,47 @@
 #![cfg_attr(process_control_docs_rs, feature(doc_cfg))]
 #![warn(unused_results)]
 
+#[cfg(feature = "log")]
+use std::ffi::OsStr;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::io;
+use std::io::Write;
+#[cfg(any(doc, unix))]
+use std::os::fd::RawFd;
 #[cfg(any(doc, unix))]
 use std::os::raw::c_int;
+#[attr_alias::eval]
+#[attr_alias(procfs, cfg(any(doc, *)))]
+use std::path::Path;
 use std::process;
 use std::process::Child;
 use std::str;
+#[cfg(any(doc, unix))]
+use std::sync::atomic::AtomicBool;
+#[cfg(any(doc, unix))]
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+#[cfg(any(doc, unix))]
+use std::sync::Arc;
+#[cfg(any(doc, unix))]
+use std::sync::Mutex;
+#[cfg(any(doc, unix))]
+use std::sync::MutexGuard;
+#[cfg(any(doc, unix))]
+use std::sync::PoisonError;
+#[cfg(any(doc, unix))]
+use std::thread;
+#[cfg(any(doc, unix))]
+use std::thread::Thread;
 use std::time::Duration;
 
 mod control;
 
+pub mod filters;
+
 #[cfg_attr(unix, path = "unix/mod.rs")]
 #[cfg_attr(windows, path = "windows/mod.rs")]
 mod imp;
@@ -128,17 +165,27 @@ macro_rules! unix_method {
 }
 
 /// Equivalent to [`process::ExitStatus`] but allows for greater accuracy.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+///
+/// Instances are compared and formatted only by their exit statuses. The
+/// [elapsed time] is ignored.
+///
+/// [elapsed time]: Self::elapsed
+#[derive(Copy, Clone, Eq)]
 #[must_use]
 pub struct ExitStatus {
     inner: imp::ExitStatus,
     std: process::ExitStatus,
+    elapsed: Option<Duration>,
 }
 
 impl ExitStatus {
     fn new(inner: imp::ExitStatus, std: process::ExitStatus) -> Self {
         debug_assert_eq!(inner, std.into());
-        Self { inner, std }
+        Self {
+            inner,
+            std,
+            elapsed: None,
+        }
     }
 
     /// Equivalent to [`process::ExitStatus::success`].
@@ -156,6 +203,42 @@ impl ExitStatus {
         self.inner.code().map(Into::into)
     }
 
+    /// Returns the time that the process ran before it was found to have
+    /// exited.
+    ///
+    /// It is measured from when the instance of [`Control`] was created,
+    /// which should be immediately after the process is spawned. The result
+    /// will be [`None`] if this instance was not created by [`Control::wait`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let time_limit = Duration::from_secs(1);
+    /// let exit_status = Command::new("echo")
+    ///     .spawn()?
+    ///     .controlled()
+    ///     .time_limit(time_limit)
+    ///     .terminate_for_timeout()
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(exit_status.success());
+    /// assert!(exit_status.elapsed().is_some_and(|x| x < time_limit));
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn elapsed(self) -> Option<Duration> {
+        self.elapsed
+    }
+
     unix_method!(continued, bool);
     unix_method!(core_dumped, bool);
     unix_method!(signal, Option<c_int>);
@@ -212,6 +295,23 @@ impl AsRef<Self> for ExitStatus {
     }
 }
 
+impl Debug for ExitStatus {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExitStatus")
+            .field("inner", &self.inner)
+            .field("std", &self.std)
+            .finish()
+    }
+}
+
+impl PartialEq for ExitStatus {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.std == other.std
+    }
+}
+
 impl Display for ExitStatus {
     #[inline]
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -314,8 +414,7 @@ impl Debug for DebugBuffer<'_> {
             let valid = str::from_utf8(string).unwrap_or_else(|error| {
                 let (valid, string) = string.split_at(error.valid_up_to());
 
-                let invalid_length =
-                    error.error_len().unwrap_or_else(|| string.len());
+                let invalid_length = error.error_len().unwrap_or(string.len());
                 invalid = &string[..invalid_length];
 
                 // SAFETY: This slice was validated to be UTF-8.
@@ -364,6 +463,374 @@ impl From<Output> for ExitStatus {
     }
 }
 
+/// The result of [`Control::wait_with_report`], which includes information
+/// that does not fit in [`Control::Result`].
+#[attr_alias::eval]
+#[derive(Clone, Eq, PartialEq)]
+#[must_use]
+#[non_exhaustive]
+pub struct Report<T> {
+    /// The result that would have been returned by [`Control::wait`].
+    pub result: Option<T>,
+
+    /// The time until the first byte was read from [stdout], if any.
+    ///
+    /// It is measured from when the instance of [`Control`] was created,
+    /// which should be immediately after the process is spawned. Bytes
+    /// rejected by filters are included, but it will only be set if the pipe
+    /// was read.
+    ///
+    /// [stdout]: process::Child::stdout
+    pub stdout_first_byte: Option<Duration>,
+
+    /// The time until the first byte was read from [stderr], if any.
+    ///
+    /// For more information, see [`stdout_first_byte`].
+    ///
+    /// [stderr]: process::Child::stderr
+    /// [`stdout_first_byte`]: Self::stdout_first_byte
+    pub stderr_first_byte: Option<Duration>,
+
+    /// The output read from each pipe passed to [`Control::extra_pipe`], in
+    /// the order they were passed.
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+    pub extra_pipes: Vec<Vec<u8>>,
+
+    /// The processes found after waiting, if
+    /// [`Control::leftover_processes`] was called.
+    #[attr_alias(procfs, cfg(any(doc, *)))]
+    #[attr_alias(procfs, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+    pub leftover_processes: Vec<LeftoverProcess>,
+}
+
+#[attr_alias::eval]
+impl<T> Report<T> {
+    const fn new(result: Option<T>) -> Self {
+        Self {
+            result,
+            stdout_first_byte: None,
+            stderr_first_byte: None,
+            #[cfg(any(doc, unix))]
+            extra_pipes: Vec::new(),
+            #[attr_alias(procfs, cfg(any(doc, *)))]
+            leftover_processes: Vec::new(),
+        }
+    }
+
+    fn with_result<U>(self, result: Option<U>) -> Report<U> {
+        Report {
+            result,
+            stdout_first_byte: self.stdout_first_byte,
+            stderr_first_byte: self.stderr_first_byte,
+            #[cfg(any(doc, unix))]
+            extra_pipes: self.extra_pipes,
+            #[attr_alias(procfs, cfg(any(doc, *)))]
+            leftover_processes: self.leftover_processes,
+        }
+    }
+}
+
+#[attr_alias::eval]
+impl<T> Debug for Report<T>
+where
+    T: Debug,
+{
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Report");
+        let _ = f
+            .field("result", &self.result)
+            .field("stdout_first_byte", &self.stdout_first_byte)
+            .field("stderr_first_byte", &self.stderr_first_byte);
+        #[cfg(any(doc, unix))]
+        let _ = f.field(
+            "extra_pipes",
+            &self
+                .extra_pipes
+                .iter()
+                .map(|x| DebugBuffer(x))
+                .collect::<Vec<_>>(),
+        );
+        #[attr_alias(procfs, cfg(any(doc, *)))]
+        let _ = f.field("leftover_processes", &self.leftover_processes);
+        f.finish()
+    }
+}
+
+/// A mode for collecting output from a specific process pipe ([stdout] or
+/// [stderr]).
+///
+/// The default mode is [`All`]. The other modes limit the memory used to
+/// collect output, which can be useful for processes that write large amounts
+/// of data.
+///
+/// Only bytes that would otherwise be included in [`Output`] are affected.
+/// Those rejected by a [`PipeFilter`] are not counted.
+///
+/// [`All`]: Self::All
+/// [stderr]: Control::stderr_capture
+/// [stdout]: Control::stdout_capture
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Capture {
+    /// Collects all output.
+    All,
+
+    /// Collects at most the given number of bytes from the end of the output.
+    Tail(usize),
+
+    /// Collects at most the given numbers of bytes from the start and end of
+    /// the output.
+    ///
+    /// If any bytes are omitted between them, a marker will be inserted in
+    /// their place. It includes the number of omitted bytes and the total
+    /// number of bytes written.
+    HeadTail(usize, usize),
+}
+
+/// An event produced by [`Events`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Event {
+    /// Bytes read from [stdout].
+    ///
+    /// [stdout]: ::std::process::Child::stdout
+    Stdout(Vec<u8>),
+
+    /// Bytes read from [stderr].
+    ///
+    /// [stderr]: ::std::process::Child::stderr
+    Stderr(Vec<u8>),
+
+    /// The process exited.
+    Exited(ExitStatus),
+
+    /// The time limit was exceeded, and the process was left running.
+    TimedOut,
+
+    /// The time limit was exceeded, and the process was terminated.
+    ///
+    /// This event is only produced when [`Control::terminate_for_timeout`] has
+    /// been called.
+    Terminated,
+}
+
+/// An iterator over the [`Event`]s of a process, created by
+/// [`Control::events`].
+///
+/// Each call to [`next`] blocks until the next event is available. The
+/// iterator ends after the process exits and all of its pipes are closed.
+///
+/// [`next`]: Self::next
+#[derive(Debug)]
+pub struct Events(mpsc::Receiver<io::Result<Event>>);
+
+impl Iterator for Events {
+    type Item = io::Result<Event>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.recv().ok()
+    }
+}
+
+/// The state of a process passed to the function set by [`Control::on_tick`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Progress {
+    /// The time elapsed since the process started being controlled.
+    pub elapsed: Duration,
+
+    /// The number of bytes read from [stdout] so far.
+    ///
+    /// Bytes are counted before being passed to any [`PipeFilter`]. Pipes
+    /// that are not read by this crate will always have a count of zero.
+    ///
+    /// [stdout]: ::std::process::Child::stdout
+    pub stdout_bytes: usize,
+
+    /// The number of bytes read from [stderr] so far.
+    ///
+    /// For more information, see [`stdout_bytes`].
+    ///
+    /// [`stdout_bytes`]: Self::stdout_bytes
+    /// [stderr]: ::std::process::Child::stderr
+    pub stderr_bytes: usize,
+}
+
+/// An action returned by the function set by [`Control::on_tick`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Decision {
+    /// Continues waiting for the process.
+    Continue,
+
+    /// Extends the time limit by the given duration.
+    ///
+    /// This action has no effect if no time limit was set.
+    ExtendTimeLimit(Duration),
+
+    /// Stops waiting for the process and terminates it.
+    ///
+    /// The process will be terminated even if
+    /// [`Control::terminate_for_timeout`] was not called, and the result will
+    /// be the same as if the time limit had been exceeded.
+    Terminate,
+}
+
+/// A process left running by a process after it exited, included in
+/// [`Report::leftover_processes`].
+#[attr_alias::eval]
+#[attr_alias(procfs, cfg(any(doc, *)))]
+#[attr_alias(procfs, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct LeftoverProcess {
+    /// The process identifier.
+    pub pid: u32,
+
+    /// The command name of the process, which may be truncated.
+    pub command: String,
+}
+
+/// A handle to pause and resume a process, which can be passed to
+/// [`Control::pausable`].
+///
+/// Requests are applied while the process is being waited on, so they have
+/// no effect before [`Control::wait`] is called or after it returns. Thus,
+/// this handle can be cloned and used from other threads. The waiting thread
+/// is woken for each request, so they are applied immediately.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::process::Command;
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use process_control::ChildExt;
+/// use process_control::Control;
+/// use process_control::PauseHandle;
+///
+/// let handle = PauseHandle::new();
+/// handle.pause();
+/// let resumer = {
+///     let handle = handle.clone();
+///     thread::spawn(move || {
+///         thread::sleep(Duration::from_secs(2));
+///         handle.resume();
+///     })
+/// };
+///
+/// let exit_status = Command::new("sleep")
+///     .arg("1")
+///     .spawn()?
+///     .controlled()
+///     .pausable(&handle)
+///     .exclude_paused_time()
+///     .time_limit(Duration::from_secs(2))
+///     .terminate_for_timeout()
+///     .wait()?
+///     .expect("process timed out");
+/// assert!(exit_status.success());
+/// # resumer.join().expect("thread panicked");
+/// #
+/// # Ok::<_, io::Error>(())
+/// ```
+#[cfg(any(doc, unix))]
+#[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+#[derive(Clone, Debug, Default)]
+pub struct PauseHandle(Arc<PauseState>);
+
+#[cfg(any(doc, unix))]
+#[derive(Debug, Default)]
+struct PauseState {
+    paused: AtomicBool,
+    // Threads waiting on processes are woken to apply requests.
+    waiters: Mutex<Vec<Thread>>,
+}
+
+#[cfg(any(doc, unix))]
+impl PauseHandle {
+    /// Creates a handle for a process that is not paused.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that the process be paused.
+    #[inline]
+    pub fn pause(&self) {
+        self.set_paused(true);
+    }
+
+    /// Requests that the process be resumed.
+    #[inline]
+    pub fn resume(&self) {
+        self.set_paused(false);
+    }
+
+    /// Returns whether the process was requested to be paused.
+    #[inline]
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::Acquire)
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.0.paused.store(paused, Ordering::Release);
+        for waiter in &*self.waiters() {
+            waiter.unpark();
+        }
+    }
+
+    fn waiters(&self) -> MutexGuard<'_, Vec<Thread>> {
+        self.0
+            .waiters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // The current thread will be unparked when requests are made.
+    pub(crate) fn add_waiter(&self) {
+        self.waiters().push(thread::current());
+    }
+
+    pub(crate) fn remove_waiter(&self) {
+        let id = thread::current().id();
+        let mut waiters = self.waiters();
+        if let Some(index) = waiters.iter().position(|x| x.id() == id) {
+            let _ = waiters.swap_remove(index);
+        }
+    }
+}
+
+/// A pipe that can be read using [`Control::extra_pipe`].
+///
+/// Instances are created by [`CommandExt::extra_pipe`].
+#[cfg(any(doc, unix))]
+#[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+#[derive(Debug)]
+pub struct ExtraPipe {
+    reader: imp::OwnedFd,
+    writer: imp::ChildFd,
+}
+
+/// A pseudoterminal that can be read using [`Control::pty`].
+///
+/// Instances are created by [`CommandExt::pty`].
+#[attr_alias::eval]
+#[attr_alias(unix_pty, cfg(any(doc, *)))]
+#[attr_alias(unix_pty, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+#[derive(Debug)]
+pub struct Pty {
+    master: imp::OwnedFd,
+    slave: imp::ChildFd,
+}
+
 /// A function to be called for reads from a specific process pipe ([stdout] or
 /// [stderr]).
 ///
@@ -448,6 +915,115 @@ pub trait Control: private::Sealed {
     #[must_use]
     fn time_limit(self, limit: Duration) -> Self;
 
+    /// Sets a time limit for the process, after which a handler will be
+    /// called.
+    ///
+    /// Unlike [`time_limit`], exceeding this limit does not stop [`wait`] from
+    /// waiting for the process. The handler is called once with the process
+    /// identifier and can be used to request diagnostics, such as sending
+    /// `SIGQUIT` to get a thread dump from the JVM. Output written afterward
+    /// is read as usual, so it will be included in [`Output`] if the process
+    /// exits before the time limit. Otherwise, [`events`] can be used to
+    /// receive it.
+    ///
+    /// The identifier will not be reused by the system while the handler is
+    /// running. If the handler returns an error, [`wait`] will stop waiting
+    /// and return it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let process = Command::new("sleep")
+    ///     .arg("1")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?;
+    ///
+    /// let output = process
+    ///     .controlled_with_output()
+    ///     .soft_time_limit(Duration::from_millis(100), |pid| {
+    ///         eprintln!("process {} is taking a while", pid);
+    ///         Ok(())
+    ///     })
+    ///     .time_limit(Duration::from_secs(5))
+    ///     .terminate_for_timeout()
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`events`]: Self::events
+    /// [`time_limit`]: Self::time_limit
+    /// [`wait`]: Self::wait
+    #[must_use]
+    fn soft_time_limit<F>(self, limit: Duration, handler: F) -> Self
+    where
+        F: 'static + FnOnce(u32) -> io::Result<()> + Send;
+
+    /// Calls a function periodically while waiting for the process.
+    ///
+    /// The function will be called each time the given interval elapses,
+    /// starting when [`wait`] is called. The [`Progress`] passed to it can be
+    /// used to report status, and the returned [`Decision`] determines
+    /// whether to keep waiting. Calls may be delayed while the process is
+    /// being created or its output is being read, so they should not be
+    /// relied on for precise timing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    /// use process_control::Decision;
+    ///
+    /// let process = Command::new("sleep")
+    ///     .arg("5")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?;
+    ///
+    /// let output = process
+    ///     .controlled_with_output()
+    ///     .on_tick(Duration::from_millis(100), |progress| {
+    ///         eprintln!(
+    ///             "read {} bytes in {:?}",
+    ///             progress.stdout_bytes, progress.elapsed,
+    ///         );
+    ///         if progress.elapsed < Duration::from_secs(1) {
+    ///             Decision::Continue
+    ///         } else {
+    ///             Decision::Terminate
+    ///         }
+    ///     })
+    ///     .wait()?;
+    /// assert_eq!(None, output);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`wait`]: Self::wait
+    #[must_use]
+    fn on_tick<F>(self, interval: Duration, tick_fn: F) -> Self
+    where
+        F: 'static + FnMut(&Progress) -> Decision + Send;
+
     /// Causes [`wait`] to never suppress an error.
     ///
     /// Typically, errors terminating the process will be ignored, as they are
@@ -462,43 +1038,994 @@ pub trait Control: private::Sealed {
     ///
     /// Process identifier reuse by the system will be mitigated. There should
     /// never be a scenario that causes an unintended process to be terminated.
+    ///
+    /// On Linux, if [`cgroup`] is called, all processes in the cgroup will be
+    /// killed together, so that pipes inherited by descendants are closed.
+    ///
+    /// [`cgroup`]: Self::cgroup
     #[must_use]
     fn terminate_for_timeout(self) -> Self;
 
-    /// Calls a filter function for each write to [stdout].
-    ///
-    /// For more information, see [`PipeFilter`].
+    /// Calls a function before the process is terminated.
     ///
-    /// # Panics
+    /// The function will be called with the process identifier when the
+    /// process is about to be terminated, such as after it exceeds the time
+    /// limit with [`terminate_for_timeout`] set. It can be used to collect
+    /// diagnostics from the running process, for example by attaching a
+    /// debugger.
     ///
-    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    /// The identifier will not be reused by the system while the function is
+    /// running. If the process exits on its own before termination, the
+    /// function will not be called. The process will be terminated even if it
+    /// returns an error, which is handled like other errors terminating the
+    /// process (see [`strict_errors`]).
     ///
-    /// [`Command::stdout`]: ::std::process::Command::stdout
-    /// [`Stdio::piped`]: ::std::process::Stdio::piped
-    /// [stdout]: Output::stdout
+    /// [`strict_errors`]: Self::strict_errors
+    /// [`terminate_for_timeout`]: Self::terminate_for_timeout
     #[must_use]
-    fn stdout_filter<T>(self, listener: T) -> Self
+    fn before_terminate<F>(self, hook: F) -> Self
     where
-        Self: Control<Result = Output>,
-        T: PipeFilter;
+        F: 'static + FnOnce(u32) -> io::Result<()> + Send;
 
-    /// Calls a filter function for each write to [stderr].
+    /// Causes all processes in the tree of the process to be killed when it
+    /// is terminated.
     ///
-    /// For more information, see [`PipeFilter`].
+    /// Killing descendants one at a time would race with any that are still
+    /// forking. Thus, the tree will be frozen first, so that no process can
+    /// escape before all are killed. The process must be a process group
+    /// leader, and the group will be stopped using `SIGSTOP`. Processes that
+    /// leave the group before termination will not be killed. On Linux,
+    /// [`cgroup`] can be called to kill a dedicated cgroup instead.
     ///
-    /// # Panics
+    /// Errors will be handled like other errors terminating the process (see
+    /// [`strict_errors`]), except that those from creating the instance will
+    /// be returned by [`wait`].
     ///
-    /// Panics if [`Command::stderr`] has not been set to [`Stdio::piped`].
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::os::unix::process::CommandExt;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let output = Command::new("sh")
+    ///     .arg("-c")
+    ///     .arg("while true; do sleep 1 & done")
+    ///     .process_group(0)
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .terminate_process_tree()
+    ///     .wait()?;
+    /// assert_eq!(None, output);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`cgroup`]: Self::cgroup
+    /// [`strict_errors`]: Self::strict_errors
+    /// [`wait`]: Self::wait
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+    #[must_use]
+    fn terminate_process_tree(self) -> Self;
+
+    /// Sets a cgroup dedicated to the process, which will be used to
+    /// terminate it along with its descendants.
+    ///
+    /// The path must be a directory in the cgroup v2 hierarchy that contains
+    /// the process, directly or in a descendant cgroup. **All processes in the
+    /// cgroup will be killed when the process is terminated**, so it should
+    /// not contain any others. The cgroup is never chosen automatically.
+    ///
+    /// Termination will write to `cgroup.kill`, if supported, to kill all
+    /// processes atomically. Otherwise, the cgroup will be frozen using
+    /// `cgroup.freeze` before they are killed, which requires Linux 5.2. This
+    /// method will apply regardless of whether [`terminate_process_tree`] is
+    /// called. The cgroup will also be frozen to pause the process for
+    /// [`pausable`].
+    ///
+    /// Errors, including those for processes not in the cgroup, will be
+    /// returned by [`wait`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs;
+    /// use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let cgroup = "/sys/fs/cgroup/process_control";
+    /// let mut process = Command::new("sh")
+    ///     .arg("-c")
+    ///     .arg("read _; while true; do sleep 1 & done")
+    ///     .stdin(Stdio::piped())
+    ///     .spawn()?;
+    /// // The process will not fork until `wait` closes its input.
+    /// fs::write(format!("{}/cgroup.procs", cgroup), process.id().to_string())?;
+    ///
+    /// let exit_status = process
+    ///     .controlled()
+    ///     .cgroup(cgroup)
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .wait()?;
+    /// assert_eq!(None, exit_status);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`pausable`]: Self::pausable
+    /// [`terminate_process_tree`]: Self::terminate_process_tree
+    /// [`wait`]: Self::wait
+    #[attr_alias(procfs, cfg(any(doc, *)))]
+    #[attr_alias(procfs, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+    #[must_use]
+    fn cgroup<T>(self, path: T) -> Self
+    where
+        T: AsRef<Path>;
+
+    /// Forwards the given signals to the process while waiting.
+    ///
+    /// While [`wait`] is running, these signals will be caught and sent to
+    /// the process being waited on. Afterward, the time limit will be reduced
+    /// to the given grace period, so that the normal termination behavior
+    /// applies if the process does not exit. Signals are delivered shortly
+    /// after being received, and they will never be sent to an unintended
+    /// process.
+    ///
+    /// Signals are caught using crate [signal-hook], which will still call
+    /// any handlers previously installed for them, so the current process is
+    /// not prevented from handling them. Signals with default actions will
+    /// not take those actions while being forwarded.
+    ///
+    /// When [`wait`] returns, the original handling of the signals is
+    /// restored. For signals with default actions, this is done by restoring
+    /// the action that signal-hook replaced, so other code should not register
+    /// handlers with it for the same signals concurrently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    ///
+    /// use libc::SIGHUP;
+    /// use libc::SIGINT;
+    /// use libc::SIGTERM;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let exit_status = Command::new("echo")
+    ///     .spawn()?
+    ///     .controlled()
+    ///     .forward_signals(&[SIGINT, SIGTERM, SIGHUP], Duration::from_secs(5))
+    ///     .terminate_for_timeout()
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(exit_status.success());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [signal-hook]: https://crates.io/crates/signal-hook
+    /// [`wait`]: Self::wait
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+    #[must_use]
+    fn forward_signals(
+        self,
+        signals: &[c_int],
+        grace_period: Duration,
+    ) -> Self;
+
+    /// Allows the process to be paused and resumed using a handle.
+    ///
+    /// Pausing will send `SIGSTOP` to the process group, if the process is
+    /// its leader, or to only the process. Resuming will send `SIGCONT`. On
+    /// Linux, if [`cgroup`] is called, that cgroup will be frozen and thawed
+    /// instead. Errors will be handled like other errors terminating the
+    /// process (see [`strict_errors`]).
+    ///
+    /// If [`wait`] returns while the process is paused, it will remain paused.
+    /// By default, paused time counts toward the time limit, but
+    /// [`exclude_paused_time`] can be called to change that.
+    ///
+    /// For an example, see [`PauseHandle`].
+    ///
+    /// [`cgroup`]: Self::cgroup
+    /// [`exclude_paused_time`]: Self::exclude_paused_time
+    /// [`strict_errors`]: Self::strict_errors
+    /// [`wait`]: Self::wait
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+    #[must_use]
+    fn pausable(self, handle: &PauseHandle) -> Self;
+
+    /// Causes time that the process is paused to not count toward the time
+    /// limit.
+    ///
+    /// This method has no effect unless [`pausable`] is also called. Only the
+    /// time limit is affected, so [`on_tick`] and [`soft_time_limit`] will
+    /// continue to use the total time elapsed.
+    ///
+    /// [`on_tick`]: Self::on_tick
+    /// [`pausable`]: Self::pausable
+    /// [`soft_time_limit`]: Self::soft_time_limit
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+    #[must_use]
+    fn exclude_paused_time(self) -> Self;
+
+    /// Causes orphaned descendants of the process to be terminated after it
+    /// exits or is terminated.
+    ///
+    /// Processes that detach from the process, for example by forking twice,
+    /// would usually be adopted by the init process and continue running.
+    /// This method makes the current process a child subreaper using
+    /// `PR_SET_CHILD_SUBREAPER`, so that those processes become its children
+    /// instead. Once the process has exited, any such children will be
+    /// killed and reaped before [`wait`] reads the remaining output.
+    ///
+    /// The process must be a process group leader, and only adopted children
+    /// that remain in its process group, or its session if it leads one, will
+    /// be killed. Other children of the current process are never affected.
+    /// Descendants orphaned before this method is called cannot be adopted.
+    ///
+    /// **The subreaper attribute applies to the entire current process.**
+    /// While any instance using this method exists, all processes orphaned
+    /// within the current process tree will be adopted by it, including those
+    /// unrelated to the process, and they might need to be reaped. The
+    /// attribute is removed once no instances remain, unless it was already
+    /// set before.
+    ///
+    /// Errors, including those for processes that are not process group
+    /// leaders, will be returned by [`wait`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::os::unix::process::CommandExt;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let output = Command::new("sh")
+    ///     .arg("-c")
+    ///     .arg("sleep 60 &")
+    ///     .process_group(0)
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .adopt_orphans()
+    ///     .time_limit(Duration::from_secs(5))
+    ///     .terminate_for_timeout()
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`wait`]: Self::wait
+    #[attr_alias(procfs, cfg(any(doc, *)))]
+    #[attr_alias(procfs, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+    #[must_use]
+    fn adopt_orphans(self) -> Self;
+
+    /// Finds the processes left running when waiting finishes, which will be
+    /// returned in [`Report::leftover_processes`].
+    ///
+    /// Processes will be found if they are in the process group or session
+    /// of the process, which requires it to be the leader. If the process is
+    /// still running, its descendants will be found as well. Those started
+    /// before the process are never included, and group and session
+    /// identifiers are ignored once the process identifier has been reused.
+    ///
+    /// If `terminate` is `true`, the processes will be killed. Unlike when
+    /// terminating the process, identifier reuse can only be fully mitigated
+    /// when the kernel supports pidfds. Errors will be handled like other
+    /// errors terminating the process (see [`strict_errors`]), except that
+    /// those from creating the instance will be returned by [`wait`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::os::unix::process::CommandExt;
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let report = Command::new("sh")
+    ///     .arg("-c")
+    ///     .arg("sleep 60 > /dev/null &")
+    ///     .process_group(0)
+    ///     .spawn()?
+    ///     .controlled()
+    ///     .leftover_processes(true)
+    ///     .time_limit(Duration::from_secs(5))
+    ///     .terminate_for_timeout()
+    ///     .wait_with_report()?;
+    /// let exit_status = report.result.expect("process timed out");
+    /// assert!(exit_status.success());
+    /// for process in report.leftover_processes {
+    ///     eprintln!("killed {} ({})", process.command, process.pid);
+    /// }
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`strict_errors`]: Self::strict_errors
+    /// [`wait`]: Self::wait
+    #[attr_alias(procfs, cfg(any(doc, *)))]
+    #[attr_alias(procfs, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+    #[must_use]
+    fn leftover_processes(self, terminate: bool) -> Self;
+
+    /// Calls a filter function for each write to [stdout].
+    ///
+    /// For more information, see [`PipeFilter`].
+    ///
+    /// If this instance yields [`ExitStatus`], the pipe will be taken from
+    /// the process and read only to call the filter. Accepted output will be
+    /// discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stdout]: Output::stdout
+    #[must_use]
+    fn stdout_filter<T>(self, listener: T) -> Self
+    where
+        T: PipeFilter;
+
+    /// Calls a filter function for each write to [stderr].
+    ///
+    /// For more information, see [`PipeFilter`].
+    ///
+    /// If this instance yields [`ExitStatus`], the pipe will be taken from
+    /// the process and read only to call the filter. Accepted output will be
+    /// discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stderr`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stderr`]: ::std::process::Command::stderr
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stderr]: Output::stderr
+    #[must_use]
+    fn stderr_filter<T>(self, listener: T) -> Self
+    where
+        T: PipeFilter;
+
+    /// Limits the number of bytes from [stdout] that will be used.
+    ///
+    /// Only the first `limit` bytes will be logged, passed to the [filter],
+    /// written or collected. Later output will still be read, so that the
+    /// process does not block writing to the pipe, but it will be discarded.
+    ///
+    /// Unlike [`filters::limit`], this method limits the bytes included in
+    /// [`Output`], even when the filter accepts the read that exceeds the
+    /// limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let output = Command::new("echo")
+    ///     .arg("foobar")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .stdout_limit(3)
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert_eq!(b"foo", &*output.stdout);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [filter]: Self::stdout_filter
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stdout]: ::std::process::Child::stdout
+    #[must_use]
+    fn stdout_limit(self, limit: usize) -> Self;
+
+    /// Limits the number of bytes from [stderr] that will be used.
+    ///
+    /// For more information, see [`stdout_limit`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stderr`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stderr`]: ::std::process::Command::stderr
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stderr]: ::std::process::Child::stderr
+    /// [`stdout_limit`]: Self::stdout_limit
+    #[must_use]
+    fn stderr_limit(self, limit: usize) -> Self;
+
+    /// Logs each line written to [stdout] using crate [log].
+    ///
+    /// Lines will be logged with the given target and level. The process
+    /// identifier and program name will be attached using the keys `pid` and
+    /// `program`, where the name will usually be the value returned by
+    /// [`Command::get_program`]. Output will be logged before being passed to
+    /// the [filter], if one is set, and it will still be collected afterward.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use log::Level;
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let mut command = Command::new("echo");
+    /// let _ = command.arg("foobar").stdout(Stdio::piped());
+    /// let exit_status = command
+    ///     .spawn()?
+    ///     .controlled()
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .log_stdout("echo", Level::Info, command.get_program())
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(exit_status.success());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`Command::get_program`]: ::std::process::Command::get_program
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [filter]: Self::stdout_filter
+    /// [log]: ::log
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stdout]: ::std::process::Child::stdout
+    #[cfg(feature = "log")]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(feature = "log")))]
+    #[must_use]
+    fn log_stdout<T>(
+        self,
+        target: &str,
+        level: log::Level,
+        program: T,
+    ) -> Self
+    where
+        T: AsRef<OsStr>;
+
+    /// Equivalent to [`log_stdout`] but for [stderr].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stderr`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stderr`]: ::std::process::Command::stderr
+    /// [`log_stdout`]: Self::log_stdout
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stderr]: ::std::process::Child::stderr
+    #[cfg(feature = "log")]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(feature = "log")))]
+    #[must_use]
+    fn log_stderr<T>(
+        self,
+        target: &str,
+        level: log::Level,
+        program: T,
+    ) -> Self
+    where
+        T: AsRef<OsStr>;
+
+    /// Writes [stdout] to a writer instead of collecting it.
+    ///
+    /// Output will first be passed to the [filter], if one is set. Accepted
+    /// bytes will then be written using [`Write::write_all`], and the writer
+    /// will be flushed when the pipe is closed. Errors will be propagated to
+    /// [`wait`]. Since output is not buffered in memory, this method is
+    /// preferable for processes that write large amounts of data.
+    ///
+    /// To keep a copy of the output in [`Output`], use [`tee_stdout`] instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let output = Command::new("echo")
+    ///     .arg("foobar")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .stdout_to(io::sink())
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert!(output.stdout.is_empty());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [filter]: Self::stdout_filter
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stdout]: Output::stdout
+    /// [`tee_stdout`]: Self::tee_stdout
+    /// [`wait`]: Self::wait
+    #[must_use]
+    fn stdout_to<T>(self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send;
+
+    /// Writes [stderr] to a writer instead of collecting it.
+    ///
+    /// For more information, see [`stdout_to`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stderr`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stderr`]: ::std::process::Command::stderr
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stderr]: Output::stderr
+    /// [`stdout_to`]: Self::stdout_to
+    #[must_use]
+    fn stderr_to<T>(self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send;
+
+    /// Writes [stdout] to a writer while also collecting it.
+    ///
+    /// This method is equivalent to [`stdout_to`], except that written bytes
+    /// will also be included in [`Output`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::io;
+    /// use std::fs::File;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let output = Command::new("echo")
+    ///     .arg("foobar")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .tee_stdout(File::create("stdout.log")?)
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stdout]: Output::stdout
+    /// [`stdout_to`]: Self::stdout_to
+    #[must_use]
+    fn tee_stdout<T>(self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send;
+
+    /// Writes [stderr] to a writer while also collecting it.
+    ///
+    /// For more information, see [`tee_stdout`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stderr`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stderr`]: ::std::process::Command::stderr
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stderr]: Output::stderr
+    /// [`tee_stdout`]: Self::tee_stdout
+    #[must_use]
+    fn tee_stderr<T>(self, writer: T) -> Self
+    where
+        Self: Control<Result = Output>,
+        T: 'static + Write + Send;
+
+    /// Sets the mode used to collect [stdout].
+    ///
+    /// For more information, see [`Capture`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    ///
+    /// use process_control::Capture;
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let output = Command::new("echo")
+    ///     .arg("foobar")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .stdout_capture(Capture::Tail(4))
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert_eq!(4, output.stdout.len());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stdout]: Output::stdout
+    #[must_use]
+    fn stdout_capture(self, capture: Capture) -> Self
+    where
+        Self: Control<Result = Output>;
+
+    /// Sets the mode used to collect [stderr].
+    ///
+    /// For more information, see [`Capture`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stderr`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stderr`]: ::std::process::Command::stderr
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stderr]: Output::stderr
+    #[must_use]
+    fn stderr_capture(self, capture: Capture) -> Self
+    where
+        Self: Control<Result = Output>;
+
+    /// Reads a pipe created by [`CommandExt::extra_pipe`] while waiting.
+    ///
+    /// Output from the pipe will be passed to the filter and collected in
+    /// [`Report::extra_pipes`], which is returned by [`wait_with_report`].
+    /// For more information, see [`PipeFilter`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::CommandExt;
+    /// use process_control::Control;
+    ///
+    /// let mut command = Command::new("sh");
+    /// let _ = command.arg("-c").arg("echo foobar >&3");
+    /// let pipe = command.extra_pipe(3)?;
+    ///
+    /// let report = command
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .extra_pipe(pipe, |_| Ok(true))
+    ///     .wait_with_report()?;
+    /// let output = report.result.expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert_eq!(b"foobar\n", &*report.extra_pipes[0]);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
     ///
-    /// [`Command::stderr`]: ::std::process::Command::stderr
-    /// [`Stdio::piped`]: ::std::process::Stdio::piped
-    /// [stderr]: Output::stderr
+    /// [`wait_with_report`]: Self::wait_with_report
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
     #[must_use]
-    fn stderr_filter<T>(self, listener: T) -> Self
+    fn extra_pipe<T>(self, pipe: ExtraPipe, filter: T) -> Self
     where
         Self: Control<Result = Output>,
         T: PipeFilter;
 
+    /// Reads a pseudoterminal created by [`CommandExt::pty`] while waiting.
+    ///
+    /// Output written to the terminal will be handled as if it was written to
+    /// [stdout], so this method should be called before methods configuring
+    /// that pipe. Since the terminal combines the output streams, [stderr]
+    /// will be empty.
+    ///
+    /// The terminal takes precedence over [`Command::stdout`], since it
+    /// replaces all standard streams of the process. Thus, if stdout was
+    /// piped, that pipe will be closed without being read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::CommandExt;
+    /// use process_control::Control;
+    ///
+    /// let mut command = Command::new("sh");
+    /// let _ = command.arg("-c").arg("test -t 1 && echo foobar");
+    /// let pty = command.pty(24, 80)?;
+    ///
+    /// let output = command
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .pty(pty)
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert_eq!(b"foobar\r\n", &*output.stdout);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [stderr]: Output::stderr
+    /// [stdout]: Output::stdout
+    #[attr_alias(unix_pty, cfg(any(doc, *)))]
+    #[attr_alias(unix_pty, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+    #[must_use]
+    fn pty(self, pty: Pty) -> Self
+    where
+        Self: Control<Result = Output>;
+
+    /// Starts an interactive [`Session`] with the process.
+    ///
+    /// The session will be able to write to [stdin] and read from [stdout],
+    /// which will not be collected until [`Session::wait`] is called. The
+    /// time limit will apply to the entire session, starting when this method
+    /// is called, and the process will be terminated if it is exceeded and
+    /// [`terminate_for_timeout`] has been called. The memory limit will be
+    /// applied immediately.
+    ///
+    /// Other pipes, such as [stderr], will be read in the background while the
+    /// session is active, so that the process cannot block writing to them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdout`] has not been set to [`Stdio::piped`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    /// use process_control::Session;
+    ///
+    /// let mut session = Command::new("perl")
+    ///     .arg("-e")
+    ///     .arg("$| = 1; print 'name? '; my $name = <STDIN>; print \"hi $name\"")
+    ///     .stdin(Stdio::piped())
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .time_limit(Duration::from_secs(5))
+    ///     .terminate_for_timeout()
+    ///     .session()?;
+    ///
+    /// let timeout = Duration::from_secs(1);
+    /// let _ = session.expect(b"name? ", timeout)?.expect("prompt timed out");
+    /// session.send(b"foobar\n")?.expect("input timed out");
+    ///
+    /// let output = session.wait()?.expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert_eq!(b"hi foobar\n", &*output.stdout);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`Command::stdout`]: ::std::process::Command::stdout
+    /// [stderr]: ::std::process::Child::stderr
+    /// [stdin]: ::std::process::Child::stdin
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    /// [stdout]: ::std::process::Child::stdout
+    /// [`terminate_for_timeout`]: Self::terminate_for_timeout
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+    fn session(
+        self,
+    ) -> io::Result<impl Session<Result = Self::Result> + Debug>;
+
+    /// Runs the process in the background, returning an iterator over its
+    /// [`Event`]s.
+    ///
+    /// Output from [stdout] and [stderr] will be produced as it is read, after
+    /// it has been passed to any [`PipeFilter`] or writer set for the pipe.
+    /// It will not be included in [`Output`]. Reading will pause whenever an
+    /// event has not been received from the iterator, so a process that
+    /// writes more output will block until the caller is ready. Limits will
+    /// still be enforced in the meantime.
+    ///
+    /// The final event will be [`Event::Exited`], [`Event::TimedOut`],
+    /// [`Event::Terminated`], or an error, as would be returned by [`wait`].
+    /// However, if the process is not waited on to completion, output written
+    /// before it was terminated might still follow that event. If the
+    /// iterator is dropped, remaining output will be discarded, but the
+    /// process will still be waited on in the background.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    /// use process_control::Event;
+    ///
+    /// let mut stdout = Vec::new();
+    /// for event in Command::new("echo")
+    ///     .arg("foobar")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .events()?
+    /// {
+    ///     match event? {
+    ///         Event::Stdout(output) => stdout.extend(output),
+    ///         Event::Exited(exit_status) => assert!(exit_status.success()),
+    ///         event => panic!("unexpected event: {:?}", event),
+    ///     }
+    /// }
+    /// assert_eq!(b"foobar", stdout.trim_ascii_end());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [stderr]: ::std::process::Child::stderr
+    /// [stdout]: ::std::process::Child::stdout
+    /// [`wait`]: Self::wait
+    fn events(self) -> io::Result<Events>
+    where
+        Self: 'static + Control<Result = Output> + Send;
+
+    /// Equivalent to [`wait`] but with additional filters for [stdout] and
+    /// [stderr] that may borrow from the caller.
+    ///
+    /// Unlike [`PipeFilter`], these filters do not need to be `'static`, since
+    /// the threads reading output will be joined before this method returns.
+    /// Each will be called before any filter set for the same pipe, which will
+    /// only receive bytes that it accepts. Filters for pipes that were not
+    /// [piped] will never be called.
+    ///
+    /// As a consequence, this method will not return until both pipes have
+    /// been closed. The process will always be terminated if the time limit
+    /// is exceeded, as if [`terminate_for_timeout`] had been called.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if [`extra_pipe`] has been called, since their output
+    /// could not be returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let mut lines = 0;
+    /// let output = Command::new("echo")
+    ///     .arg("foo\nbar")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?
+    ///     .controlled_with_output()
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .wait_scoped(
+    ///         |x| {
+    ///             lines += x.iter().filter(|&&x| x == b'\n').count();
+    ///             Ok(false)
+    ///         },
+    ///         |_| Ok(true),
+    ///     )?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert!(output.stdout.is_empty());
+    /// assert_eq!(2, lines);
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`extra_pipe`]: Self::extra_pipe
+    /// [piped]: ::std::process::Stdio::piped
+    /// [stderr]: Self::stderr_filter
+    /// [stdout]: Self::stdout_filter
+    /// [`terminate_for_timeout`]: Self::terminate_for_timeout
+    /// [`wait`]: Self::wait
+    fn wait_scoped<T, U>(
+        self,
+        stdout_filter: T,
+        stderr_filter: U,
+    ) -> WaitResult<Self::Result>
+    where
+        Self: Control<Result = Output>,
+        T: FnMut(&[u8]) -> io::Result<bool> + Send,
+        U: FnMut(&[u8]) -> io::Result<bool> + Send;
+
     /// Runs the process to completion, aborting if it exceeds the time limit.
     ///
     /// At least one additional thread might be created to wait on the process
@@ -521,6 +2048,89 @@ pub trait Control: private::Sealed {
     ///
     /// [`terminate_for_timeout`]: Self::terminate_for_timeout
     fn wait(self) -> WaitResult<Self::Result>;
+
+    /// Equivalent to [`wait`], but additional information will be returned
+    /// in a [`Report`].
+    ///
+    /// The report will be returned even if the time limit is exceeded, in
+    /// which case [`Report::result`] will be [`None`].
+    ///
+    /// [`wait`]: Self::wait
+    fn wait_with_report(self) -> io::Result<Report<Self::Result>>;
+}
+
+/// An interactive session with a process, created by [`Control::session`].
+///
+/// Each method that waits on the process returns `Ok(None)` if its timeout or
+/// the time limit of the session is exceeded. In the latter case, all
+/// subsequent calls will also return `Ok(None)`.
+#[cfg(any(doc, unix))]
+#[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+pub trait Session: private::Sealed {
+    /// The type returned by [`wait`].
+    ///
+    /// [`wait`]: Self::wait
+    type Result;
+
+    /// Writes all bytes to [stdin].
+    ///
+    /// This method will only wait for the time limit of the session.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Command::stdin`] has not been set to [`Stdio::piped`].
+    ///
+    /// [`Command::stdin`]: ::std::process::Command::stdin
+    /// [stdin]: ::std::process::Child::stdin
+    /// [`Stdio::piped`]: ::std::process::Stdio::piped
+    fn send(&mut self, input: &[u8]) -> WaitResult<()>;
+
+    /// Waits for a pattern to be written to [stdout].
+    ///
+    /// All output up to and including the first match will be removed and
+    /// returned. Matches are only searched for in output that has not been
+    /// returned by a previous call.
+    ///
+    /// If the pipe is closed before a match is found, an error with kind
+    /// [`io::ErrorKind::UnexpectedEof`] will be returned.
+    ///
+    /// [stdout]: ::std::process::Child::stdout
+    fn expect(
+        &mut self,
+        pattern: &[u8],
+        timeout: Duration,
+    ) -> WaitResult<Vec<u8>>;
+
+    /// Waits for [stdout] to satisfy a predicate.
+    ///
+    /// This method is equivalent to [`expect`], except that the predicate
+    /// determines whether a match has been found. It will be called with all
+    /// unmatched output whenever more is read and should return the length
+    /// of the output to remove, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the predicate returns a length greater than that of the
+    /// output passed to it.
+    ///
+    /// [`expect`]: Self::expect
+    /// [stdout]: ::std::process::Child::stdout
+    fn expect_fn<F>(
+        &mut self,
+        matcher: F,
+        timeout: Duration,
+    ) -> WaitResult<Vec<u8>>
+    where
+        F: FnMut(&[u8]) -> Option<usize>;
+
+    /// Ends the session and waits for the process to exit.
+    ///
+    /// This method is equivalent to [`Control::wait`], but the remaining time
+    /// limit of the session will be used. Any output that has not been
+    /// returned by [`expect`] will be included in [`Output`].
+    ///
+    /// [`expect`]: Self::expect
+    fn wait(self) -> WaitResult<Self::Result>;
 }
 
 /// Extensions to [`Child`] for easily terminating processes.
@@ -593,6 +2203,189 @@ pub trait ChildExt: private::Sealed {
     /// ```
     #[must_use]
     fn controlled_with_output(self) -> impl Control<Result = Output> + Debug;
+
+    /// Equivalent to [`controlled_with_output`] but borrows the process.
+    ///
+    /// The piped handles will be taken from the process when output is
+    /// collected, but the process will otherwise remain usable afterward. For
+    /// example, it can be waited on again if the time limit is exceeded,
+    /// although output written after that point will not be collected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::process::Command;
+    /// use std::process::Stdio;
+    /// use std::time::Duration;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::Control;
+    ///
+    /// let mut process = Command::new("echo")
+    ///     .arg("foobar")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?;
+    /// let output = process
+    ///     .controlled_with_output_mut()
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(output.status.success());
+    /// assert!(process.stdout.is_none());
+    /// assert!(process.try_wait()?.is_some_and(|x| x.success()));
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`controlled_with_output`]: Self::controlled_with_output
+    #[must_use]
+    fn controlled_with_output_mut(
+        &mut self,
+    ) -> impl Control<Result = Output> + Debug;
+
+    /// Sends a signal to the process.
+    ///
+    /// Like [`Child::kill`], this method mitigates process identifier reuse
+    /// by the system, so the signal will never be sent to an unintended
+    /// process. It will be sent using a pidfd when the platform supports it.
+    ///
+    /// If the process has already exited, it will be reaped, and an error with
+    /// kind [`InvalidInput`] will be returned. The exit status will be
+    /// included in the error message and can also be retrieved using
+    /// [`Child::try_wait`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// use std::os::unix::process::ExitStatusExt;
+    /// use std::process::Command;
+    ///
+    /// use process_control::ChildExt;
+    ///
+    /// let mut process = Command::new("sleep").arg("5").spawn()?;
+    /// process.send_signal(libc::SIGTERM)?;
+    /// assert_eq!(Some(libc::SIGTERM), process.wait()?.signal());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    ///
+    /// [`InvalidInput`]: io::ErrorKind::InvalidInput
+    #[cfg(any(doc, unix))]
+    #[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+    fn send_signal(&mut self, signal: c_int) -> io::Result<()>;
+}
+
+/// Extensions to [`Command`] for configuring processes to be controlled.
+///
+/// [`Command`]: process::Command
+#[attr_alias::eval]
+#[cfg(any(doc, unix))]
+#[cfg_attr(process_control_docs_rs, doc(cfg(unix)))]
+pub trait CommandExt: private::Sealed {
+    /// Creates a pipe that the process can write to using the given file
+    /// descriptor.
+    ///
+    /// The returned pipe should be passed to [`Control::extra_pipe`] after
+    /// spawning the process. Until then, the write end remains open in the
+    /// current process. Afterward, spawning the command again will fail.
+    ///
+    /// The descriptor should not be a standard stream or be open in the
+    /// current process. Otherwise, other descriptors might be overwritten in
+    /// the process.
+    ///
+    /// For an example, see [`Control::extra_pipe`].
+    fn extra_pipe(&mut self, fd: RawFd) -> io::Result<ExtraPipe>;
+
+    /// Creates a pseudoterminal with the given window size and uses it for
+    /// all standard streams of the process.
+    ///
+    /// The process will be started in a new session, with the terminal as its
+    /// controlling terminal. Many programs change their behavior when writing
+    /// to a terminal, for example by adding colors or disabling buffering.
+    ///
+    /// The returned terminal should be passed to [`Control::pty`] after
+    /// spawning the process. Until then, the terminal remains open in the
+    /// current process. Afterward, spawning the command again will fail.
+    ///
+    /// For an example, see [`Control::pty`].
+    #[attr_alias(unix_pty, cfg(any(doc, *)))]
+    #[attr_alias(unix_pty, cfg_attr(process_control_docs_rs, doc(cfg(*))))]
+    fn pty(&mut self, rows: u16, columns: u16) -> io::Result<Pty>;
+
+    /// Causes the process to receive the given signal when the current thread
+    /// exits.
+    ///
+    /// This method uses `PR_SET_PDEATHSIG`, which is typically used to
+    /// terminate the process if the current process crashes or is killed
+    /// before it can be terminated normally. If the current process has
+    /// already exited when the process starts, the signal will be sent
+    /// immediately.
+    ///
+    /// The signal is sent when the thread that spawned the process exits, not
+    /// the entire current process. Thus, the process should usually be
+    /// spawned by a thread that will run for as long as the process might.
+    ///
+    /// If the signal is invalid, spawning the process will fail with error
+    /// code `EINVAL`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    ///
+    /// use libc::SIGKILL;
+    ///
+    /// use process_control::ChildExt;
+    /// use process_control::CommandExt;
+    /// use process_control::Control;
+    ///
+    /// let exit_status = Command::new("echo")
+    ///     .parent_death_signal(SIGKILL)
+    ///     .spawn()?
+    ///     .controlled()
+    ///     .time_limit(Duration::from_secs(1))
+    ///     .terminate_for_timeout()
+    ///     .wait()?
+    ///     .expect("process timed out");
+    /// assert!(exit_status.success());
+    /// #
+    /// # Ok::<_, io::Error>(())
+    /// ```
+    #[attr_alias(parent_death_signal, cfg(any(doc, *)))]
+    #[attr_alias(
+        parent_death_signal,
+        cfg_attr(process_control_docs_rs, doc(cfg(*)))
+    )]
+    fn parent_death_signal(&mut self, signal: c_int) -> &mut Self;
+}
+
+#[attr_alias::eval]
+#[cfg(any(doc, unix))]
+impl CommandExt for process::Command {
+    #[inline]
+    fn extra_pipe(&mut self, fd: RawFd) -> io::Result<ExtraPipe> {
+        imp::extra_pipe(self, fd)
+            .map(|(reader, writer)| ExtraPipe { reader, writer })
+    }
+
+    #[attr_alias(unix_pty, cfg(any(doc, *)))]
+    #[inline]
+    fn pty(&mut self, rows: u16, columns: u16) -> io::Result<Pty> {
+        imp::pty(self, rows, columns)
+            .map(|(master, slave)| Pty { master, slave })
+    }
+
+    #[attr_alias(parent_death_signal, cfg(any(doc, *)))]
+    #[inline]
+    fn parent_death_signal(&mut self, signal: c_int) -> &mut Self {
+        imp::parent_death_signal(self, signal);
+        self
+    }
 }
 
 impl ChildExt for Child {
@@ -605,14 +2398,31 @@ impl ChildExt for Child {
     fn controlled_with_output(self) -> impl Control<Result = Output> + Debug {
         control::Buffer::new(self)
     }
+
+    #[inline]
+    fn controlled_with_output_mut(
+        &mut self,
+    ) -> impl Control<Result = Output> + Debug {
+        control::Buffer::new(control::BorrowedChild(self))
+    }
+
+    #[cfg(any(doc, unix))]
+    #[inline]
+    fn send_signal(&mut self, signal: c_int) -> io::Result<()> {
+        imp::send_signal(self, signal)
+    }
 }
 
 mod private {
     use std::process::Child;
+    use std::process::Command;
 
     use super::control;
 
     pub trait Sealed {}
     impl Sealed for Child {}
+    impl Sealed for Command {}
     impl<P> Sealed for control::Buffer<P> where P: control::Process {}
+    #[cfg(unix)]
+    impl<P> Sealed for control::Session<P> where P: control::Process {}
 }
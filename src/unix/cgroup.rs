@@ -0,0 +1,166 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::time::Duration;
+use std::time::Instant;
+
+use libc::pid_t;
+use libc::ESRCH;
+use libc::SIGKILL;
+
+use super::check_syscall;
+
+// Freezing can be delayed by processes in uninterruptible sleep.
+const FREEZE_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn read_cgroup(pid: &str) -> io::Result<Option<String>> {
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", pid))?;
+    // The unified hierarchy always has identifier 0.
+    Ok(cgroups
+        .lines()
+        .find_map(|x| x.strip_prefix("0::"))
+        .map(str::to_owned))
+}
+
+fn find_mount() -> io::Result<Option<(String, PathBuf)>> {
+    let mounts = fs::read_to_string("/proc/self/mountinfo")?;
+    Ok(mounts.lines().find_map(|mount| {
+        let (fields, filesystem) = mount.split_once(" - ")?;
+        if filesystem.split(' ').next()? != "cgroup2" {
+            return None;
+        }
+        // Fields are numbered as in proc_pid_mountinfo(5).
+        let mut fields = fields.split(' ').skip(3);
+        let root = fields.next()?;
+        let mount_point = fields.next()?;
+        Some((root.to_owned(), mount_point.into()))
+    }))
+}
+
+fn read_processes(path: &Path, processes: &mut Vec<pid_t>) -> io::Result<()> {
+    for pid in fs::read_to_string(path.join("cgroup.procs"))?.lines() {
+        processes.push(pid.parse().map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "invalid process list")
+        })?);
+    }
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            read_processes(&entry.path(), processes)?;
+        }
+    }
+    Ok(())
+}
+
+fn find_path(pid: pid_t) -> io::Result<Option<PathBuf>> {
+    let Some(cgroup) = read_cgroup(&pid.to_string())? else {
+        return Ok(None);
+    };
+    let Some((root, mount_point)) = find_mount()? else {
+        return Ok(None);
+    };
+
+    // The cgroup might not be visible from the mount.
+    Ok(cgroup
+        .strip_prefix(root.trim_end_matches('/'))
+        .map(|x| mount_point.join(x.trim_start_matches('/'))))
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    // The cgroup is never guessed, since killing all processes in it is only
+    // safe if the caller knows that it is dedicated to the process.
+    pub(crate) fn new(path: &Path, process: &Child) -> io::Result<Self> {
+        let pid = process.id().try_into().expect("process identifier is invalid");
+        let path = fs::canonicalize(path)?;
+        // The freezer requires Linux 5.2.
+        if !path.join("cgroup.freeze").exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cgroup does not support freezing",
+            ));
+        }
+        if !find_path(pid)?
+            .map(fs::canonicalize)
+            .transpose()?
+            .is_some_and(|x| x.starts_with(&path))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "process is not in the cgroup",
+            ));
+        }
+        Ok(Self { path })
+    }
+
+    pub(super) fn freeze(&self, frozen: bool) -> io::Result<()> {
+        let value = if frozen { "1" } else { "0" };
+        fs::write(self.path.join("cgroup.freeze"), value)?;
+        if !frozen {
+            return Ok(());
+        }
+
+        // Changes to this file are reported as priority events by [poll].
+        let mut file = File::open(self.path.join("cgroup.events"))?;
+        let start = Instant::now();
+        loop {
+            let mut events = String::new();
+            let _ = file.seek(SeekFrom::Start(0))?;
+            let _ = file.read_to_string(&mut events)?;
+            if events.lines().any(|x| x == "frozen 1") {
+                return Ok(());
+            }
+
+            let Some(timeout) = FREEZE_TIMEOUT.checked_sub(start.elapsed())
+            else {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "cgroup was not frozen",
+                ));
+            };
+            let _ = super::read::poll_priority(&file, Some(timeout))?;
+        }
+    }
+
+    // The cgroup should be frozen, so that processes cannot be added and
+    // their identifiers cannot be reused.
+    fn kill_processes(&self) -> io::Result<()> {
+        let mut processes = Vec::new();
+        read_processes(&self.path, &mut processes)?;
+        for pid in processes {
+            match check_syscall(unsafe { libc::kill(pid, SIGKILL) }) {
+                Err(error) if error.raw_os_error() != Some(ESRCH) => {
+                    return Err(error);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the method used, so that it can be traced.
+    pub(super) fn kill(&self) -> io::Result<&'static str> {
+        // Linux 5.14 can kill all processes atomically.
+        let path = self.path.join("cgroup.kill");
+        if path.exists() {
+            return fs::write(path, "1").map(|()| "cgroup.kill");
+        }
+
+        let mut result = self.freeze(true).and_then(|()| self.kill_processes());
+        let next_result = self.freeze(false);
+        if result.is_ok() {
+            result = next_result;
+        }
+        result.map(|()| "cgroup.freeze")
+    }
+}
@@ -0,0 +1,220 @@
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::fd::RawFd;
+#[attr_alias::eval]
+#[attr_alias(parent_death_signal)]
+use std::os::raw::c_int;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use libc::EBADF;
+use libc::F_SETFD;
+
+use super::check_syscall;
+
+#[cfg(any(
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "illumos",
+    target_os = "linux",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "redox",
+    target_os = "solaris",
+))]
+fn pipe() -> io::Result<[OwnedFd; 2]> {
+    use libc::O_CLOEXEC;
+
+    let mut fds = [0; 2];
+    check_syscall(unsafe { libc::pipe2(fds.as_mut_ptr(), O_CLOEXEC) })?;
+    Ok(fds.map(|x| unsafe { OwnedFd::from_raw_fd(x) }))
+}
+
+#[cfg(not(any(
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "illumos",
+    target_os = "linux",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "redox",
+    target_os = "solaris",
+)))]
+fn pipe() -> io::Result<[OwnedFd; 2]> {
+    let mut fds = [0; 2];
+    check_syscall(unsafe { libc::pipe(fds.as_mut_ptr()) })?;
+    let fds = fds.map(|x| unsafe { OwnedFd::from_raw_fd(x) });
+    for fd in &fds {
+        set_cloexec(fd)?;
+    }
+    Ok(fds)
+}
+
+#[attr_alias::eval]
+#[attr_alias(
+    unix_pty,
+    cfg(any(
+        *,
+        not(any(
+            target_os = "android",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "illumos",
+            target_os = "linux",
+            target_os = "netbsd",
+            target_os = "openbsd",
+            target_os = "redox",
+            target_os = "solaris",
+        )),
+    ))
+)]
+fn set_cloexec(fd: &OwnedFd) -> io::Result<()> {
+    use libc::FD_CLOEXEC;
+
+    check_syscall(unsafe { libc::fcntl(fd.as_raw_fd(), F_SETFD, FD_CLOEXEC) })
+}
+
+/// A descriptor that is only valid in the child while the parent keeps it
+/// open.
+#[derive(Debug)]
+pub(crate) struct ChildFd {
+    inner: OwnedFd,
+    closed: Arc<AtomicBool>,
+}
+
+impl ChildFd {
+    fn new(fd: OwnedFd) -> Self {
+        Self {
+            inner: fd,
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // The function is called after forking, so it must be async-signal-safe.
+    unsafe fn pre_exec<F>(&self, command: &mut Command, map_fn: F)
+    where
+        F: 'static + Fn(RawFd) -> io::Result<()> + Send + Sync,
+    {
+        let closed = Arc::clone(&self.closed);
+        let raw_fd = self.inner.as_raw_fd();
+        let _ = unsafe {
+            command.pre_exec(move || {
+                // The command might be spawned again after the parent closed
+                // this descriptor, and it might have been reassigned since.
+                if closed.load(Ordering::Acquire) {
+                    return Err(io::Error::from_raw_os_error(EBADF));
+                }
+                map_fn(raw_fd)
+            })
+        };
+    }
+}
+
+impl Drop for ChildFd {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Release);
+    }
+}
+
+pub(crate) fn extra_pipe(
+    command: &mut Command,
+    fd: RawFd,
+) -> io::Result<(OwnedFd, ChildFd)> {
+    let [reader, writer] = pipe()?;
+    let writer = ChildFd::new(writer);
+    unsafe {
+        writer.pre_exec(command, move |raw_fd| {
+            if raw_fd == fd {
+                check_syscall(libc::fcntl(fd, F_SETFD, 0))
+            } else {
+                check_syscall(libc::dup2(raw_fd, fd))
+            }
+        });
+    }
+    Ok((reader, writer))
+}
+
+#[attr_alias::eval]
+#[attr_alias(unix_pty)]
+pub(crate) fn pty(
+    command: &mut Command,
+    rows: u16,
+    columns: u16,
+) -> io::Result<(OwnedFd, ChildFd)> {
+    use std::ptr;
+
+    use libc::winsize;
+    use libc::STDERR_FILENO;
+    use libc::STDIN_FILENO;
+    use libc::STDOUT_FILENO;
+    use libc::TIOCSCTTY;
+
+    let mut master = 0;
+    let mut slave = 0;
+    check_syscall(unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut winsize {
+                ws_row: rows,
+                ws_col: columns,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            },
+        )
+    })?;
+    let master = unsafe { OwnedFd::from_raw_fd(master) };
+    let slave = unsafe { OwnedFd::from_raw_fd(slave) };
+    set_cloexec(&master)?;
+    set_cloexec(&slave)?;
+
+    let slave = ChildFd::new(slave);
+    unsafe {
+        slave.pre_exec(command, |raw_fd| {
+            // The terminal can only become the controlling terminal of a
+            // session leader.
+            check_syscall(libc::setsid())?;
+            #[allow(clippy::useless_conversion)]
+            check_syscall(libc::ioctl(raw_fd, TIOCSCTTY.into(), 0))?;
+            for fd in [STDIN_FILENO, STDOUT_FILENO, STDERR_FILENO] {
+                check_syscall(libc::dup2(raw_fd, fd))?;
+            }
+            Ok(())
+        });
+    }
+    Ok((master, slave))
+}
+
+#[attr_alias::eval]
+#[attr_alias(parent_death_signal)]
+pub(crate) fn parent_death_signal(command: &mut Command, signal: c_int) {
+    use libc::c_ulong;
+    use libc::EINVAL;
+    use libc::PR_SET_PDEATHSIG;
+
+    // Invalid signals are reported when spawning, like those rejected by the
+    // system.
+    let death_signal = c_ulong::try_from(signal).ok();
+    let parent = unsafe { libc::getpid() };
+    let _ = unsafe {
+        command.pre_exec(move || {
+            let death_signal = death_signal
+                .ok_or_else(|| io::Error::from_raw_os_error(EINVAL))?;
+            check_syscall(libc::prctl(PR_SET_PDEATHSIG, death_signal))?;
+            // The parent might have exited before the signal was set.
+            if libc::getppid() != parent {
+                check_syscall(libc::raise(signal))?;
+            }
+            Ok(())
+        })
+    };
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::io;
+use std::process::Child;
+
+use libc::pid_t;
+use libc::ESRCH;
+use libc::SIGKILL;
+
+use crate::LeftoverProcess;
+
+use super::check_syscall;
+use super::pidfd::Pidfd;
+use super::procfs::Stat;
+
+fn ignore_exited(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(error) if error.raw_os_error() == Some(ESRCH) => Ok(()),
+        result => result,
+    }
+}
+
+// Processes that are not children can only be signaled without races using a
+// pidfd, since their identifiers might be reused at any time.
+fn kill(process: &Stat) -> io::Result<()> {
+    let is_same = || match Stat::new(process.pid) {
+        Ok(stat) => Ok(stat.start_time == process.start_time),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error),
+    };
+
+    let pidfd = match Pidfd::open(process.pid) {
+        Ok(Some(pidfd)) => pidfd,
+        // Older kernels do not support pidfds, so a small race is
+        // unavoidable.
+        Ok(None) => {
+            return if is_same()? {
+                ignore_exited(check_syscall(unsafe {
+                    libc::kill(process.pid, SIGKILL)
+                }))
+            } else {
+                Ok(())
+            };
+        }
+        Err(error) => return ignore_exited(Err(error)),
+    };
+
+    // The identifier might have been reused before the pidfd was opened.
+    if !is_same()? {
+        return Ok(());
+    }
+    ignore_exited(pidfd.send_signal(SIGKILL))
+}
+
+#[derive(Debug)]
+pub(crate) struct Leftovers {
+    pid: pid_t,
+    start_time: u64,
+    processes: Vec<Stat>,
+}
+
+impl Leftovers {
+    pub(crate) fn new(process: &Child) -> io::Result<Self> {
+        let pid = process.id().try_into().expect("process identifier is invalid");
+        Stat::new(pid).map(|x| Self {
+            pid,
+            start_time: x.start_time,
+            processes: Vec::new(),
+        })
+    }
+
+    pub(crate) fn find(&mut self) -> io::Result<Vec<LeftoverProcess>> {
+        let processes = super::procfs::processes()?;
+        // Ancestry can only be checked while the process is running, since
+        // its identifier might be reused afterward. Group and session
+        // identifiers cannot be reused while any process has them, but the
+        // new process might create them again, so this must be checked in the
+        // same snapshot.
+        let mut running = false;
+        let mut reused = false;
+        for process in processes.iter().filter(|x| x.pid == self.pid) {
+            if process.start_time == self.start_time {
+                running = true;
+            } else {
+                reused = true;
+            }
+        }
+        let parents: HashMap<_, _> =
+            processes.iter().map(|x| (x.pid, x.parent)).collect();
+        let is_descendant = |mut pid| {
+            // Limit the search in case the ancestry changed while it was read.
+            for _ in 0..parents.len() {
+                match parents.get(&pid) {
+                    Some(&parent) if parent == self.pid => return true,
+                    Some(&parent) => pid = parent,
+                    None => break,
+                }
+            }
+            false
+        };
+
+        self.processes = processes
+            .into_iter()
+            .filter(|x| {
+                x.pid != self.pid
+                    && x.start_time >= self.start_time
+                    && ((!reused
+                        && (x.group == self.pid || x.session == self.pid))
+                        || (running && is_descendant(x.pid)))
+            })
+            .collect();
+        Ok(self
+            .processes
+            .iter()
+            .map(|x| LeftoverProcess {
+                pid: x.pid.try_into().expect("process identifier is invalid"),
+                command: x.command.clone(),
+            })
+            .collect())
+    }
+
+    pub(crate) fn terminate(self) -> io::Result<()> {
+        self.processes.iter().try_for_each(kill)
+    }
+}
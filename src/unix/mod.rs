@@ -10,6 +10,16 @@ use libc::__rlimit_resource_t;
 
 use super::WaitResult;
 
+macro_rules! if_pty {
+    ( $($item:item)+ ) => {
+    $(
+        #[::attr_alias::eval]
+        #[attr_alias(unix_pty)]
+        $item
+    )+
+    };
+}
+
 macro_rules! if_waitid {
     ( $($item:item)+ ) => {
     $(
@@ -20,11 +30,54 @@ macro_rules! if_waitid {
     };
 }
 
+mod command;
+pub(super) use command::extra_pipe;
+if_pty! {
+    pub(super) use command::pty;
+}
+#[attr_alias::eval]
+#[attr_alias(parent_death_signal)]
+pub(super) use command::parent_death_signal;
+pub(super) use command::ChildFd;
+
 mod exit_status;
 pub(super) use exit_status::ExitStatus;
 
 mod read;
-pub(super) use read::read2;
+pub(super) use read::poll_read;
+pub(super) use read::poll_write;
+pub(super) use read::read_nonblocking;
+pub(super) use read::read_pipes;
+pub(super) use read::set_nonblocking;
+
+mod signal;
+pub(super) use signal::SignalForwarder;
+
+attr_alias::eval_block! {
+    #[attr_alias(procfs)]
+    mod cgroup;
+    #[attr_alias(procfs)]
+    pub(super) use cgroup::Cgroup;
+
+    #[attr_alias(procfs)]
+    mod procfs;
+
+    #[attr_alias(procfs)]
+    mod pidfd;
+
+    #[attr_alias(procfs)]
+    mod leftovers;
+    #[attr_alias(procfs)]
+    pub(super) use leftovers::Leftovers;
+
+    #[attr_alias(procfs)]
+    mod subreaper;
+    #[attr_alias(procfs)]
+    pub(super) use subreaper::Subreaper;
+}
+
+mod tree;
+pub(super) use tree::ProcessTree;
 
 mod wait;
 
@@ -87,6 +140,31 @@ fn check_syscall(result: c_int) -> io::Result<()> {
     }
 }
 
+#[attr_alias::eval]
+pub(super) fn send_signal(
+    process: &mut Child,
+    signal: c_int,
+) -> io::Result<()> {
+    // The identifier cannot be reused until the process is reaped, which
+    // requires mutable access to it.
+    if let Some(exit_status) = process.try_wait()? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("process has already exited ({})", exit_status),
+        ));
+    }
+
+    let pid = process
+        .id()
+        .try_into()
+        .expect("process identifier is invalid");
+    #[attr_alias(procfs)]
+    if let Some(pidfd) = pidfd::Pidfd::open(pid)? {
+        return pidfd.send_signal(signal);
+    }
+    check_syscall(unsafe { libc::kill(pid, signal) })
+}
+
 #[attr_alias::eval]
 #[attr_alias(raw_pid)]
 #[derive(Debug)]
@@ -173,10 +251,10 @@ impl<'a> Process<'a> {
         self.set_limit(RLIMIT_AS, limit)
     }
 
-    pub(super) fn wait(
-        &mut self,
-        time_limit: Option<Duration>,
-    ) -> WaitResult<ExitStatus> {
-        wait::wait(self, time_limit)
+    pub(super) fn wait<I>(&mut self, time_limits: I) -> WaitResult<ExitStatus>
+    where
+        I: Iterator<Item = Option<Duration>>,
+    {
+        wait::wait(self, time_limits)
     }
 }
@@ -0,0 +1,48 @@
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::raw::c_int;
+use std::ptr;
+
+use libc::c_long;
+use libc::pid_t;
+use libc::ENOSYS;
+use libc::SYS_pidfd_open;
+use libc::SYS_pidfd_send_signal;
+
+fn check_long_syscall(result: c_long) -> io::Result<c_int> {
+    c_int::try_from(result)
+        .ok()
+        .filter(|&x| x >= 0)
+        .ok_or_else(io::Error::last_os_error)
+}
+
+#[derive(Debug)]
+pub(super) struct Pidfd(OwnedFd);
+
+impl Pidfd {
+    // Returns [None] if the kernel does not support pidfds.
+    pub(super) fn open(pid: pid_t) -> io::Result<Option<Self>> {
+        match check_long_syscall(unsafe {
+            libc::syscall(SYS_pidfd_open, pid, 0)
+        }) {
+            Ok(pidfd) => Ok(Some(Self(unsafe { OwnedFd::from_raw_fd(pidfd) }))),
+            Err(error) if error.raw_os_error() == Some(ENOSYS) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub(super) fn send_signal(&self, signal: c_int) -> io::Result<()> {
+        check_long_syscall(unsafe {
+            libc::syscall(
+                SYS_pidfd_send_signal,
+                self.0.as_raw_fd(),
+                signal,
+                ptr::null::<()>(),
+                0,
+            )
+        })
+        .map(drop)
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::io;
+use std::str;
+
+use libc::pid_t;
+
+fn invalid_stat() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid process status")
+}
+
+#[derive(Debug)]
+pub(super) struct Stat {
+    pub(super) pid: pid_t,
+    pub(super) command: String,
+    pub(super) parent: pid_t,
+    pub(super) group: pid_t,
+    pub(super) session: pid_t,
+    pub(super) start_time: u64,
+}
+
+impl Stat {
+    fn parse(stat: &[u8]) -> Option<Self> {
+        // The command name is enclosed in parentheses, but it can contain any
+        // character, so the last parenthesis must be found.
+        let open = stat.iter().position(|&x| x == b'(')?;
+        let close = stat.iter().rposition(|&x| x == b')')?;
+        let pid = str::from_utf8(&stat[..open]).ok()?.trim().parse().ok()?;
+        let command = String::from_utf8_lossy(stat.get(open + 1..close)?);
+
+        let fields: Vec<_> = str::from_utf8(stat.get(close + 1..)?)
+            .ok()?
+            .split_ascii_whitespace()
+            .collect();
+        // Fields are numbered as in proc(5), where the first after the
+        // command name is 3.
+        let field = |number: usize| fields.get(number - 3);
+        Some(Self {
+            pid,
+            command: command.into_owned(),
+            parent: field(4)?.parse().ok()?,
+            group: field(5)?.parse().ok()?,
+            session: field(6)?.parse().ok()?,
+            start_time: field(22)?.parse().ok()?,
+        })
+    }
+
+    pub(super) fn new(pid: pid_t) -> io::Result<Self> {
+        let stat = fs::read(format!("/proc/{}/stat", pid))?;
+        Self::parse(&stat).ok_or_else(invalid_stat)
+    }
+}
+
+pub(super) fn processes() -> io::Result<Vec<Stat>> {
+    let mut processes = Vec::new();
+    for entry in fs::read_dir("/proc")? {
+        let Ok(pid) = entry?.file_name().to_string_lossy().parse() else {
+            continue;
+        };
+        match Stat::new(pid) {
+            Ok(stat) => processes.push(stat),
+            // The process might have exited.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(processes)
+}
@@ -19,39 +19,142 @@ use std::io;
 use std::io::Read;
 use std::os::fd::AsRawFd;
 use std::os::fd::RawFd;
+use std::os::raw::c_int;
+use std::os::raw::c_short;
+use std::time::Duration;
 
 use libc::fcntl;
 use libc::pollfd;
+use libc::EIO;
 use libc::F_GETFL;
 use libc::F_SETFL;
 use libc::O_NONBLOCK;
 use libc::POLLIN;
+use libc::POLLOUT;
+#[attr_alias::eval]
+#[attr_alias(procfs)]
+use libc::POLLPRI;
 
 use crate::control::Pipe;
+use crate::control::ReadBuffer;
 
-impl Pipe {
-    fn set_nonblocking(&mut self) -> io::Result<()> {
-        let fd = self.as_raw_fd();
-        let flags = unsafe { fcntl(fd, F_GETFL) };
-        super::check_syscall(flags)?;
-        super::check_syscall(unsafe { fcntl(fd, F_SETFL, flags | O_NONBLOCK) })
-    }
+pub(crate) fn set_nonblocking<T>(fd: &T) -> io::Result<()>
+where
+    T: AsRawFd,
+{
+    let fd = fd.as_raw_fd();
+    let flags = unsafe { fcntl(fd, F_GETFL) };
+    super::check_syscall(flags)?;
+    super::check_syscall(unsafe { fcntl(fd, F_SETFL, flags | O_NONBLOCK) })
+}
+
+// Filters are called after each read, so output is only buffered until this
+// many bytes have been read.
+const MAX_READ_LENGTH: usize = 1 << 16;
+
+pub(crate) fn read_nonblocking<R>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+) -> io::Result<bool>
+where
+    R: AsRawFd + Read,
+{
+    let result = reader
+        .by_ref()
+        .take(MAX_READ_LENGTH as u64)
+        .read_to_end(buffer);
+    // The pipe will be read again if it is ready.
+    result.map(|x| x == MAX_READ_LENGTH).or_else(|error| {
+        if error.kind() == io::ErrorKind::WouldBlock {
+            Ok(true)
+        } else if error.raw_os_error() == Some(EIO)
+            && unsafe { libc::isatty(reader.as_raw_fd()) } == 1
+        {
+            // Pseudoterminal masters report this error after the terminal has
+            // been closed.
+            Ok(false)
+        } else {
+            Err(error)
+        }
+    })
+}
+
+fn poll<T>(
+    fd: &T,
+    events: c_short,
+    timeout: Option<Duration>,
+) -> io::Result<bool>
+where
+    T: AsRawFd,
+{
+    let mut fd = pollfd {
+        fd: fd.as_raw_fd(),
+        events,
+        revents: 0,
+    };
+    let timeout = timeout.map_or(-1, |x| {
+        x.as_nanos()
+            .div_ceil(1_000_000)
+            .try_into()
+            .unwrap_or(c_int::MAX)
+    });
+    super::check_syscall(unsafe { libc::poll(&mut fd, 1, timeout) })
+        .map(|()| fd.revents != 0)
+        .or_else(|error| {
+            if error.kind() == io::ErrorKind::Interrupted {
+                Ok(false)
+            } else {
+                Err(error)
+            }
+        })
 }
 
-impl AsRawFd for Pipe {
+pub(crate) fn poll_read<T>(
+    fd: &T,
+    timeout: Option<Duration>,
+) -> io::Result<bool>
+where
+    T: AsRawFd,
+{
+    poll(fd, POLLIN, timeout)
+}
+
+pub(crate) fn poll_write<T>(
+    fd: &T,
+    timeout: Option<Duration>,
+) -> io::Result<bool>
+where
+    T: AsRawFd,
+{
+    poll(fd, POLLOUT, timeout)
+}
+
+#[attr_alias::eval]
+#[attr_alias(procfs)]
+pub(super) fn poll_priority<T>(
+    fd: &T,
+    timeout: Option<Duration>,
+) -> io::Result<bool>
+where
+    T: AsRawFd,
+{
+    poll(fd, POLLPRI, timeout)
+}
+
+impl AsRawFd for Pipe<'_> {
     fn as_raw_fd(&self) -> RawFd {
         self.inner.as_raw_fd()
     }
 }
 
 struct AsyncPipe<'a> {
-    inner: Pipe,
-    buffer: &'a mut Vec<u8>,
+    inner: Pipe<'a>,
+    buffer: &'a mut ReadBuffer,
 }
 
 impl<'a> AsyncPipe<'a> {
-    fn new(mut pipe: Pipe, buffer: &'a mut Vec<u8>) -> io::Result<Self> {
-        pipe.set_nonblocking()?;
+    fn new(pipe: Pipe<'a>, buffer: &'a mut ReadBuffer) -> io::Result<Self> {
+        set_nonblocking(&pipe)?;
         Ok(Self {
             inner: pipe,
             buffer,
@@ -59,29 +162,24 @@ impl<'a> AsyncPipe<'a> {
     }
 
     fn next_result(&mut self) -> io::Result<bool> {
-        let index = self.buffer.len();
-        let result = self
-            .inner
-            .inner
-            .read_to_end(self.buffer)
-            .map(|_| false)
-            .or_else(|error| {
-                if error.kind() == io::ErrorKind::WouldBlock {
-                    Ok(true)
-                } else {
-                    Err(error)
-                }
-            })?;
-        if self.buffer.len() != index {
+        let index = self.buffer.inner.len();
+        let result =
+            read_nonblocking(&mut self.inner.inner, &mut self.buffer.inner)?;
+        if self.buffer.inner.len() != index {
             self.inner.run_filter(self.buffer, index)?;
         }
+        if !result {
+            self.inner.finish(self.buffer)?;
+        }
         Ok(result)
     }
 }
 
-pub(crate) fn read2(pipes: [Option<Pipe>; 2]) -> io::Result<[Vec<u8>; 2]> {
-    const EMPTY_BUFFER: Vec<u8> = Vec::new();
-    let mut buffers = [EMPTY_BUFFER; 2];
+pub(crate) fn read_pipes(
+    pipes: Vec<Option<Pipe<'_>>>,
+) -> io::Result<Vec<ReadBuffer>> {
+    let mut buffers: Vec<_> =
+        pipes.iter().map(|_| ReadBuffer::default()).collect();
 
     let mut pipes: Vec<_> = pipes
         .into_iter()
@@ -98,13 +196,13 @@ pub(crate) fn read2(pipes: [Option<Pipe>; 2]) -> io::Result<[Vec<u8>; 2]> {
         })
         .collect();
 
-    let mut start = 0;
-    debug_assert!(fds.len() <= 2);
-    let mut length: u8 = fds.len() as _;
-
-    while length != 0 {
+    while !fds.is_empty() {
         let result = super::check_syscall(unsafe {
-            libc::poll(fds.as_mut_ptr().add(start), length.into(), -1)
+            libc::poll(
+                fds.as_mut_ptr(),
+                fds.len().try_into().expect("too many pipes"),
+                -1,
+            )
         });
         if let Err(error) = result {
             if error.kind() != io::ErrorKind::Interrupted {
@@ -113,12 +211,14 @@ pub(crate) fn read2(pipes: [Option<Pipe>; 2]) -> io::Result<[Vec<u8>; 2]> {
             continue;
         }
 
-        for i in (start..).take(length.into()) {
+        // Iterating in reverse allows finished pipes to be removed.
+        for i in (0..fds.len()).rev() {
             if fds[i].revents != 0 && !pipes[i].next_result()? {
-                start = i ^ 1;
-                length -= 1;
+                let _ = fds.swap_remove(i);
+                let _ = pipes.swap_remove(i);
             }
         }
     }
+    drop(pipes);
     Ok(buffers)
 }
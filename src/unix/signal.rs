@@ -0,0 +1,143 @@
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::raw::c_int;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use libc::sigaction;
+use libc::SIG_DFL;
+
+use signal_hook::iterator::Signals;
+
+use super::check_syscall;
+
+fn replace_action(
+    signal: c_int,
+    action: Option<&sigaction>,
+) -> io::Result<sigaction> {
+    let mut old_action = MaybeUninit::uninit();
+    check_syscall(unsafe {
+        libc::sigaction(
+            signal,
+            action.map_or(ptr::null(), ptr::from_ref),
+            old_action.as_mut_ptr(),
+        )
+    })?;
+    Ok(unsafe { old_action.assume_init() })
+}
+
+struct Disposition {
+    signal: c_int,
+    forwarders: usize,
+    // signal-hook ignores signals with default actions when no handlers are
+    // registered with it, so the default action must be restored while no
+    // signals are being forwarded.
+    restores_default: bool,
+    // The action installed by signal-hook while it is replaced.
+    hook_action: Option<sigaction>,
+}
+
+// signal-hook never removes the handlers it installs, so they must be
+// replaced and reinstalled as needed.
+static DISPOSITIONS: Mutex<Vec<Disposition>> = Mutex::new(Vec::new());
+
+fn acquire_one(
+    dispositions: &mut Vec<Disposition>,
+    signal: c_int,
+) -> io::Result<()> {
+    let index = if let Some(index) =
+        dispositions.iter().position(|x| x.signal == signal)
+    {
+        index
+    } else {
+        let action = replace_action(signal, None)?;
+        dispositions.push(Disposition {
+            signal,
+            forwarders: 0,
+            restores_default: action.sa_sigaction == SIG_DFL,
+            hook_action: None,
+        });
+        dispositions.len() - 1
+    };
+
+    let disposition = &mut dispositions[index];
+    if let Some(action) = &disposition.hook_action {
+        let _ = replace_action(signal, Some(action))?;
+        disposition.hook_action = None;
+    }
+    disposition.forwarders += 1;
+    Ok(())
+}
+
+fn acquire(signals: &[c_int]) -> io::Result<()> {
+    let mut dispositions =
+        DISPOSITIONS.lock().unwrap_or_else(PoisonError::into_inner);
+    for (i, &signal) in signals.iter().enumerate() {
+        if let Err(error) = acquire_one(&mut dispositions, signal) {
+            drop(dispositions);
+            release(&signals[..i]);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+fn release(signals: &[c_int]) {
+    let mut dispositions =
+        DISPOSITIONS.lock().unwrap_or_else(PoisonError::into_inner);
+    for &signal in signals {
+        let disposition = dispositions
+            .iter_mut()
+            .find(|x| x.signal == signal)
+            .expect("missing disposition");
+        disposition.forwarders -= 1;
+        if disposition.forwarders != 0 || !disposition.restores_default {
+            continue;
+        }
+        // SAFETY: An all-zero action is valid, and it is only used to set
+        // the default action.
+        let mut action: sigaction =
+            unsafe { MaybeUninit::zeroed().assume_init() };
+        action.sa_sigaction = SIG_DFL;
+        disposition.hook_action = replace_action(signal, Some(&action)).ok();
+    }
+}
+
+pub(crate) struct SignalForwarder {
+    signals: Signals,
+    forwarded: Vec<c_int>,
+}
+
+impl SignalForwarder {
+    pub(crate) fn new(signals: &[c_int]) -> io::Result<Self> {
+        acquire(signals)?;
+        match Signals::new(signals) {
+            Ok(forwarded_signals) => Ok(Self {
+                signals: forwarded_signals,
+                forwarded: signals.to_owned(),
+            }),
+            Err(error) => {
+                release(signals);
+                Err(error)
+            }
+        }
+    }
+
+    // The process must not have been reaped.
+    pub(crate) fn forward(&mut self, pid: u32) -> io::Result<bool> {
+        let pid = pid.try_into().expect("process identifier is invalid");
+        let mut forwarded = false;
+        for signal in self.signals.pending() {
+            check_syscall(unsafe { libc::kill(pid, signal) })?;
+            forwarded = true;
+        }
+        Ok(forwarded)
+    }
+}
+
+impl Drop for SignalForwarder {
+    fn drop(&mut self) {
+        release(&self.forwarded);
+    }
+}
@@ -0,0 +1,117 @@
+use std::io;
+use std::process::Child;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use libc::c_int;
+use libc::c_ulong;
+use libc::pid_t;
+use libc::ECHILD;
+use libc::EINTR;
+use libc::PR_GET_CHILD_SUBREAPER;
+use libc::PR_SET_CHILD_SUBREAPER;
+use libc::SIGKILL;
+
+use super::check_syscall;
+use super::procfs::Stat;
+
+// The number of active instances, and whether the current process was
+// already a subreaper before the first was created.
+static SUBREAPERS: Mutex<(usize, bool)> = Mutex::new((0, false));
+
+fn set_subreaper(subreaper: bool) -> io::Result<()> {
+    check_syscall(unsafe {
+        libc::prctl(PR_SET_CHILD_SUBREAPER, c_ulong::from(subreaper))
+    })
+}
+
+#[derive(Debug)]
+pub(crate) struct Subreaper {
+    pid: pid_t,
+    session_leader: bool,
+    start_time: u64,
+}
+
+impl Subreaper {
+    pub(crate) fn new(process: &Child) -> io::Result<Self> {
+        let pid = process.id().try_into().expect("process identifier is invalid");
+        let stat = Stat::new(pid)?;
+        if stat.group != pid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "process is not a process group leader",
+            ));
+        }
+
+        let mut subreapers =
+            SUBREAPERS.lock().unwrap_or_else(PoisonError::into_inner);
+        let (count, was_subreaper) = &mut *subreapers;
+        if *count == 0 {
+            let mut subreaper: c_int = 0;
+            check_syscall(unsafe {
+                libc::prctl(PR_GET_CHILD_SUBREAPER, &mut subreaper)
+            })?;
+            *was_subreaper = subreaper != 0;
+            if !*was_subreaper {
+                set_subreaper(true)?;
+            }
+        }
+        *count += 1;
+
+        Ok(Self {
+            pid,
+            session_leader: stat.session == pid,
+            start_time: stat.start_time,
+        })
+    }
+
+    // The controlled process must have been reaped, so that it is not found.
+    pub(crate) fn reap(self) -> io::Result<()> {
+        let current_pid = unsafe { libc::getpid() };
+        loop {
+            // Only children that remained in the process group or session of
+            // the process can be known to have been adopted from it.
+            let orphans: Vec<_> = super::procfs::processes()?
+                .into_iter()
+                .filter(|x| {
+                    x.parent == current_pid
+                        && x.start_time >= self.start_time
+                        && (x.group == self.pid
+                            || (self.session_leader && x.session == self.pid))
+                })
+                .map(|x| x.pid)
+                .collect();
+            if orphans.is_empty() {
+                return Ok(());
+            }
+
+            // Identifiers of children cannot be reused until they are reaped.
+            for pid in orphans {
+                check_syscall(unsafe { libc::kill(pid, SIGKILL) })?;
+                while let Err(error) = check_syscall(unsafe {
+                    libc::waitpid(pid, ptr::null_mut(), 0)
+                }) {
+                    match error.raw_os_error() {
+                        Some(EINTR) => {}
+                        // The process was reaped elsewhere.
+                        Some(ECHILD) => break,
+                        _ => return Err(error),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Subreaper {
+    fn drop(&mut self) {
+        let mut subreapers =
+            SUBREAPERS.lock().unwrap_or_else(PoisonError::into_inner);
+        let (count, was_subreaper) = &mut *subreapers;
+        *count -= 1;
+        if *count == 0 && !*was_subreaper {
+            let _ = set_subreaper(false);
+        }
+    }
+}
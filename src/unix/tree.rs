@@ -0,0 +1,103 @@
+use std::io;
+use std::os::raw::c_int;
+use std::process::Child;
+
+use libc::pid_t;
+use libc::ESRCH;
+use libc::SIGCONT;
+use libc::SIGKILL;
+use libc::SIGSTOP;
+
+#[attr_alias::eval]
+#[attr_alias(procfs)]
+use super::cgroup::Cgroup;
+use super::check_syscall;
+
+fn signal_group(group: pid_t, signal: c_int) -> io::Result<()> {
+    match check_syscall(unsafe { libc::kill(-group, signal) }) {
+        Err(error) if error.raw_os_error() == Some(ESRCH) => Ok(()),
+        result => result,
+    }
+}
+
+#[attr_alias::eval]
+#[derive(Debug)]
+enum Members {
+    #[attr_alias(procfs)]
+    Cgroup(Cgroup),
+    Group(pid_t),
+    Process(pid_t),
+}
+
+#[derive(Debug)]
+pub(crate) struct ProcessTree(Members);
+
+#[attr_alias::eval]
+#[attr_alias(procfs)]
+impl From<Cgroup> for ProcessTree {
+    fn from(value: Cgroup) -> Self {
+        Self(Members::Cgroup(value))
+    }
+}
+
+#[attr_alias::eval]
+impl ProcessTree {
+    pub(crate) fn new(process: &Child) -> io::Result<Self> {
+        let tree = Self::find(process)?;
+        if let Members::Process(_) = tree.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "process is not a process group leader",
+            ));
+        }
+        Ok(tree)
+    }
+
+    // Unlike [new], this method falls back to only the process.
+    pub(crate) fn find(process: &Child) -> io::Result<Self> {
+        let pid = process
+            .id()
+            .try_into()
+            .expect("process identifier is invalid");
+        let group = unsafe { libc::getpgid(pid) };
+        check_syscall(group)?;
+        Ok(Self(if group == pid {
+            Members::Group(group)
+        } else {
+            Members::Process(pid)
+        }))
+    }
+
+    // The process must not have been reaped, since its identifier is used for
+    // the process group. The method used is returned, so that it can be
+    // traced.
+    pub(crate) fn terminate(self) -> io::Result<&'static str> {
+        match self.0 {
+            #[attr_alias(procfs)]
+            Members::Cgroup(cgroup) => cgroup.kill(),
+            // Stopped processes cannot fork or leave the group, and they will
+            // be woken by SIGKILL.
+            Members::Group(group) => {
+                signal_group(group, SIGSTOP)?;
+                signal_group(group, SIGKILL).map(|()| "SIGSTOP")
+            }
+            Members::Process(pid) => {
+                check_syscall(unsafe { libc::kill(pid, SIGKILL) })
+                    .map(|()| "SIGKILL")
+            }
+        }
+    }
+
+    // The process must not have been reaped.
+    pub(crate) fn suspend(&self, suspended: bool) -> io::Result<()> {
+        let signal = if suspended { SIGSTOP } else { SIGCONT };
+        match self.0 {
+            #[attr_alias(procfs)]
+            Members::Cgroup(ref cgroup) => cgroup.freeze(suspended),
+            Members::Group(group) => signal_group(group, signal),
+            Members::Process(pid) => {
+                check_syscall(unsafe { libc::kill(pid, signal) })
+            }
+        }
+    }
+}
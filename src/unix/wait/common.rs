@@ -1,4 +1,5 @@
 use std::io;
+use std::iter;
 use std::mem;
 use std::mem::ManuallyDrop;
 use std::ops::Deref;
@@ -95,10 +96,13 @@ where
     DropBuffer(ManuallyDrop::new(drop_fn))
 }
 
-pub(in super::super) fn wait(
+pub(in super::super) fn wait<I>(
     process: &mut Process<'_>,
-    time_limit: Option<Duration>,
-) -> WaitResult<ExitStatus> {
+    time_limits: I,
+) -> WaitResult<ExitStatus>
+where
+    I: Iterator<Item = Option<Duration>>,
+{
     // SAFETY: The process is removed by [_guard] before this function returns.
     let process = Arc::new(Mutex::new(Some(unsafe {
         transmute_lifetime_mut(process.inner)
@@ -107,6 +111,20 @@ pub(in super::super) fn wait(
         let _ = MutexGuard::lock(&process, false).take();
     });
 
+    // The identifier passed to callbacks of the iterator must remain valid
+    // while they are running, so the process cannot be reaped then.
+    let mut time_limits = time_limits;
+    let time_limits = iter::from_fn(|| {
+        let mut process = MutexGuard::lock(&process, false);
+        let process = process.as_mut().expect("missing process");
+        if let Ok(None) = process.try_wait() {
+            time_limits.next()
+        } else {
+            // The waiting thread will return the result.
+            Some(None)
+        }
+    });
+
     let process = Arc::clone(&process);
     super::run_with_time_limit(
         move || {
@@ -126,7 +144,7 @@ pub(in super::super) fn wait(
                 while signals.wait().count() == 0 {}
             }
         },
-        time_limit,
+        time_limits,
     )?
     .transpose()
 }
@@ -1,4 +1,5 @@
 use std::sync::mpsc;
+use std::sync::mpsc::TryRecvError;
 use std::thread;
 use std::time::Duration;
 
@@ -28,20 +29,40 @@ attr_alias::eval_block! {
     pub(super) use imp::wait;
 }
 
-fn run_with_time_limit<F, R>(
-    run_fn: F,
-    time_limit: Option<Duration>,
-) -> WaitResult<R>
+fn run_with_time_limit<F, R, I>(run_fn: F, mut time_limits: I) -> WaitResult<R>
 where
     F: 'static + FnOnce() -> R + Send,
     R: 'static + Send,
+    I: Iterator<Item = Option<Duration>>,
 {
-    let Some(time_limit) = time_limit else {
+    let Some(time_limit) = time_limits.next() else {
+        return Ok(None);
+    };
+    // Without a time limit, the iterator would not be used again until the
+    // process exits.
+    let Some(mut time_limit) = time_limit else {
         return Ok(Some(run_fn()));
     };
 
+    // This thread can also be unparked by [PauseHandle], so the iterator
+    // must be used again after any wakeup.
+    let waiter = thread::current();
     let (result_sender, result_receiver) = mpsc::channel();
-    thread::Builder::new()
-        .spawn(move || result_sender.send(run_fn()))
-        .map(|_| result_receiver.recv_timeout(time_limit).ok())
+    let _ = thread::Builder::new().spawn(move || {
+        let _ = result_sender.send(run_fn());
+        waiter.unpark();
+    })?;
+    loop {
+        thread::park_timeout(time_limit);
+        match result_receiver.try_recv() {
+            Ok(result) => return Ok(Some(result)),
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => return Ok(None),
+        }
+        match time_limits.next() {
+            Some(Some(next_time_limit)) => time_limit = next_time_limit,
+            Some(None) => return Ok(result_receiver.recv().ok()),
+            None => return Ok(None),
+        }
+    }
 }
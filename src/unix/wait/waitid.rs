@@ -4,7 +4,6 @@ use std::time::Duration;
 use libc::P_PID;
 use libc::WEXITED;
 use libc::WNOWAIT;
-use libc::WSTOPPED;
 
 use crate::WaitResult;
 
@@ -12,10 +11,13 @@ use super::super::check_syscall;
 use super::super::ExitStatus;
 use super::super::Process;
 
-pub(in super::super) fn wait(
+pub(in super::super) fn wait<I>(
     process: &mut Process<'_>,
-    time_limit: Option<Duration>,
-) -> WaitResult<ExitStatus> {
+    time_limits: I,
+) -> WaitResult<ExitStatus>
+where
+    I: Iterator<Item = Option<Duration>>,
+{
     let pid = process.pid.as_id();
     super::run_with_time_limit(
         move || loop {
@@ -25,12 +27,12 @@ pub(in super::super) fn wait(
                     P_PID,
                     pid,
                     process_info.as_mut_ptr(),
-                    WEXITED | WNOWAIT | WSTOPPED,
+                    WEXITED | WNOWAIT,
                 )
             }));
             break Ok(unsafe { ExitStatus::new(process_info.assume_init()) });
         },
-        time_limit,
+        time_limits,
     )?
     .transpose()
 }
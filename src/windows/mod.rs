@@ -1,12 +1,9 @@
 use std::io;
-use std::iter::FusedIterator;
-use std::num::NonZeroU32;
 use std::os::windows::io::AsRawHandle;
 use std::os::windows::io::OwnedHandle;
 use std::process::Child;
 use std::ptr;
 use std::time::Duration;
-use std::time::Instant;
 
 use windows_sys::core::BOOL;
 use windows_sys::Win32::Foundation::CloseHandle;
@@ -33,7 +30,7 @@ mod exit_status;
 pub(super) use exit_status::ExitStatus;
 
 mod read;
-pub(super) use read::read2;
+pub(super) use read::read_pipes;
 
 macro_rules! assert_matches {
     ( $result:expr , $expected_result:pat ) => {{
@@ -109,45 +106,6 @@ impl Drop for JobHandle {
     }
 }
 
-struct TimeLimits {
-    time_limit: Option<Duration>,
-    start: Instant,
-}
-
-impl TimeLimits {
-    fn new(time_limit: Option<Duration>) -> Self {
-        Self {
-            time_limit,
-            start: Instant::now(),
-        }
-    }
-}
-
-impl FusedIterator for TimeLimits {}
-
-impl Iterator for TimeLimits {
-    type Item = NonZeroU32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let Some(time_limit) = self.time_limit else {
-            const NON_ZERO_INFINITE: NonZeroU32 =
-                NonZeroU32::new(INFINITE).unwrap();
-
-            return Some(NON_ZERO_INFINITE);
-        };
-
-        let mut time_limit = time_limit
-            .saturating_sub(self.start.elapsed())
-            .as_millis()
-            .try_into()
-            .unwrap_or(u32::MAX);
-        if time_limit == INFINITE {
-            time_limit -= 1;
-        }
-        NonZeroU32::new(time_limit)
-    }
-}
-
 #[derive(Debug)]
 pub(super) struct Process<'a> {
     inner: &'a mut Child,
@@ -226,16 +184,22 @@ impl<'a> Process<'a> {
         })
     }
 
-    pub(super) fn wait(
-        &mut self,
-        time_limit: Option<Duration>,
-    ) -> WaitResult<ExitStatus> {
+    pub(super) fn wait<I>(&mut self, time_limits: I) -> WaitResult<ExitStatus>
+    where
+        I: Iterator<Item = Option<Duration>>,
+    {
         // https://github.com/rust-lang/rust/blob/49c68bd53f90e375bfb3cbba8c1c67a9e0adb9c0/src/libstd/sys/windows/process.rs#L334-L344
 
-        for time_limit in TimeLimits::new(time_limit) {
-            match unsafe {
-                WaitForSingleObject(self.handle.0, time_limit.get())
-            } {
+        for time_limit in time_limits {
+            let time_limit = time_limit.map_or(INFINITE, |x| {
+                // Rounding up prevents waking before the time limit.
+                x.as_nanos()
+                    .div_ceil(1_000_000)
+                    .try_into()
+                    .unwrap_or(u32::MAX)
+                    .min(INFINITE - 1)
+            });
+            match unsafe { WaitForSingleObject(self.handle.0, time_limit) } {
                 WAIT_OBJECT_0 => {
                     return self
                         .get_exit_code()
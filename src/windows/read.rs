@@ -43,6 +43,7 @@ use windows_sys::Win32::System::IO::OVERLAPPED;
 use windows_sys::Win32::System::IO::OVERLAPPED_0;
 
 use crate::control::Pipe;
+use crate::control::ReadBuffer;
 
 macro_rules! static_assert {
     ( $condition:expr ) => {
@@ -104,7 +105,7 @@ const fn u32_to_usize(n: u32) -> usize {
     n as usize
 }
 
-impl Pipe {
+impl Pipe<'_> {
     fn raw(&self) -> HANDLE {
         self.inner.as_raw_handle()
     }
@@ -133,15 +134,15 @@ impl Pipe {
 }
 
 struct AsyncPipe<'a> {
-    inner: Pipe,
+    inner: Pipe<'a>,
     event: ManuallyDrop<Event>,
-    buffer: &'a mut Vec<u8>,
+    buffer: &'a mut ReadBuffer,
     reading: bool,
 }
 
 impl<'a> AsyncPipe<'a> {
-    fn new(pipe: Pipe, buffer: &'a mut Vec<u8>) -> io::Result<Self> {
-        debug_assert!(buffer.is_empty());
+    fn new(pipe: Pipe<'a>, buffer: &'a mut ReadBuffer) -> io::Result<Self> {
+        debug_assert!(buffer.inner.is_empty());
 
         Ok(Self {
             inner: pipe,
@@ -152,18 +153,22 @@ impl<'a> AsyncPipe<'a> {
     }
 
     unsafe fn finish_read(&mut self, read_length: usize) -> io::Result<bool> {
-        debug_assert!(read_length <= self.buffer.spare_capacity_mut().len());
+        debug_assert!(
+            read_length <= self.buffer.inner.spare_capacity_mut().len(),
+        );
 
-        let index = self.buffer.len();
+        let index = self.buffer.inner.len();
         unsafe {
-            self.buffer.set_len(index + read_length);
+            self.buffer.inner.set_len(index + read_length);
         }
         let eof = read_length == 0;
-        if !eof {
-            self.buffer.reserve(1);
+        self.reading = false;
+        if eof {
+            self.inner.finish(self.buffer)?;
+        } else {
+            self.buffer.inner.reserve(1);
             self.inner.run_filter(self.buffer, index)?;
         }
-        self.reading = false;
         Ok(!eof)
     }
 
@@ -179,7 +184,7 @@ impl<'a> AsyncPipe<'a> {
     fn read_overlapped(&mut self) -> io::Result<Option<usize>> {
         debug_assert!(!self.reading);
 
-        let buffer = self.buffer.spare_capacity_mut();
+        let buffer = self.buffer.inner.spare_capacity_mut();
         let max_length = buffer.len().try_into().unwrap_or(u32::MAX);
         let mut length = 0;
         super::check_syscall(unsafe {
@@ -226,7 +231,7 @@ impl Drop for AsyncPipe<'_> {
             // Upon failure, overlapped IO operations may still be in progress,
             // so leaking memory is required to ensure that pointers remain
             // valid.
-            mem::forget(mem::take(self.buffer));
+            mem::forget(mem::take(&mut self.buffer.inner));
         } else {
             unsafe {
                 ManuallyDrop::drop(&mut self.event);
@@ -235,8 +240,13 @@ impl Drop for AsyncPipe<'_> {
     }
 }
 
-pub(crate) fn read2(pipes: [Option<Pipe>; 2]) -> io::Result<[Vec<u8>; 2]> {
-    let mut buffers = [(); 2].map(|()| Vec::with_capacity(32));
+pub(crate) fn read_pipes(
+    pipes: Vec<Option<Pipe<'_>>>,
+) -> io::Result<Vec<ReadBuffer>> {
+    let mut buffers: Vec<_> = pipes
+        .iter()
+        .map(|_| ReadBuffer::with_capacity(32))
+        .collect();
 
     let mut pipes: Vec<_> = pipes
         .into_iter()
@@ -244,30 +254,21 @@ pub(crate) fn read2(pipes: [Option<Pipe>; 2]) -> io::Result<[Vec<u8>; 2]> {
         .filter_map(|(pipe, buffer)| pipe.map(|x| AsyncPipe::new(x, buffer)))
         .collect::<Result<_, _>>()?;
 
-    let events: Vec<_> = pipes.iter().map(|x| x.event.hEvent).collect();
+    let mut events: Vec<_> = pipes.iter().map(|x| x.event.hEvent).collect();
 
-    let mut start = 0;
-    debug_assert!(events.len() <= 2);
-    let mut length = events.len() as _;
-
-    while length != 0 {
-        let mut index = unsafe {
-            WaitForMultipleObjects(
-                length,
-                events.as_ptr().add(start),
-                FALSE,
-                INFINITE,
-            )
+    while !events.is_empty() {
+        let length = events.len().try_into().expect("too many pipes");
+        let index = unsafe {
+            WaitForMultipleObjects(length, events.as_ptr(), FALSE, INFINITE)
         }
         .checked_sub(WAIT_OBJECT_0)
         .filter(|&x| x < length)
         .map(|x| x as usize)
         .ok_or_else(io::Error::last_os_error)?;
 
-        index += start;
         if !pipes[index].next_result()? {
-            start = index ^ 1;
-            length -= 1;
+            let _ = events.swap_remove(index);
+            let _ = pipes.swap_remove(index);
         }
     }
     drop(pipes);
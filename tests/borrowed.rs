@@ -0,0 +1,85 @@
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::io;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use process_control::ChildExt;
+use process_control::Control;
+
+#[allow(unused_macros)]
+mod common;
+use common::LONG_TIME_LIMIT;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static MAX_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+// Tracks the memory used by this test, which is the only one in this file.
+struct Allocator;
+
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let allocated =
+                ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+            let _ = MAX_ALLOCATED
+                .fetch_max(allocated + layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        let _ = ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
+#[test]
+fn test_filter_discards_output() -> io::Result<()> {
+    const LENGTH: usize = 1 << 26;
+
+    let mut process = Command::new("perl")
+        .arg("-e")
+        .arg("print 'a' x 65536 for 1 .. $ARGV[0] / 65536")
+        .arg("--")
+        .arg(LENGTH.to_string())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let length = Arc::new(AtomicUsize::new(0));
+    let control = process
+        .controlled()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .stdout_filter({
+            let length = Arc::clone(&length);
+            move |x| {
+                let _ = length.fetch_add(x.len(), Ordering::Relaxed);
+                Ok(true)
+            }
+        });
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    MAX_ALLOCATED.store(allocated, Ordering::Relaxed);
+    let exit_status = control.wait()?.expect("process timed out");
+    assert_eq!(Some(0), exit_status.code());
+    assert_eq!(LENGTH, length.load(Ordering::Relaxed));
+
+    // Accepted output should not be buffered.
+    let max_allocated = MAX_ALLOCATED.load(Ordering::Relaxed) - allocated;
+    assert!(
+        max_allocated < LENGTH / 16,
+        "{} bytes allocated",
+        max_allocated
+    );
+
+    Ok(())
+}
@@ -0,0 +1,287 @@
+#![cfg(any(target_os = "android", target_os = "linux"))]
+
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use libc::O_CLOEXEC;
+use libc::O_WRONLY;
+
+use process_control::ChildExt;
+use process_control::Control;
+use process_control::PauseHandle;
+
+#[allow(unused_macros)]
+mod common;
+#[cfg(feature = "tracing")]
+use common::recorder::Recorder;
+use common::LONG_TIME_LIMIT;
+
+struct Cgroup(PathBuf);
+
+impl Cgroup {
+    // Returns [None] if cgroups cannot be created, such as when the tests are
+    // not run as root.
+    fn new(name: &str) -> io::Result<Option<Self>> {
+        let cgroup = Self::create(name)?;
+        if cgroup.is_none() {
+            eprintln!("skipping test: cgroups cannot be created");
+        }
+        Ok(cgroup)
+    }
+
+    fn create(name: &str) -> io::Result<Option<Self>> {
+        let mounts = fs::read_to_string("/proc/self/mountinfo")?;
+        let Some(mount_point) = mounts.lines().find_map(|x| {
+            let (fields, filesystem) = x.split_once(" - ")?;
+            filesystem
+                .starts_with("cgroup2 ")
+                .then(|| fields.split(' ').nth(4))
+                .flatten()
+        }) else {
+            return Ok(None);
+        };
+        let cgroups = fs::read_to_string("/proc/self/cgroup")?;
+        let Some(cgroup) = cgroups.lines().find_map(|x| x.strip_prefix("0::"))
+        else {
+            return Ok(None);
+        };
+
+        let path = Path::new(mount_point)
+            .join(cgroup.trim_start_matches('/'))
+            .join(format!("process_control-{}-{}", process::id(), name));
+        match fs::create_dir(&path) {
+            Ok(()) => Ok(Some(Self(path))),
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::PermissionDenied
+                        | io::ErrorKind::ReadOnlyFilesystem,
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn spawn(&self, command: &mut Command) -> io::Result<process::Child> {
+        let procs =
+            CString::new(self.0.join("cgroup.procs").as_os_str().as_bytes())
+                .expect("invalid cgroup path");
+        // Only async-signal-safe functions can be called after forking.
+        unsafe {
+            command.pre_exec(move || {
+                let fd = libc::open(procs.as_ptr(), O_WRONLY | O_CLOEXEC);
+                if fd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let result = libc::write(fd, b"0".as_ptr().cast(), 1);
+                let error = io::Error::last_os_error();
+                let _ = libc::close(fd);
+                if result < 0 {
+                    return Err(error);
+                }
+                Ok(())
+            })
+        }
+        .spawn()
+    }
+
+    fn processes(&self) -> io::Result<String> {
+        fs::read_to_string(self.0.join("cgroup.procs"))
+    }
+
+    fn assert_empty(&self) -> io::Result<()> {
+        let start = Instant::now();
+        while !self.processes()?.is_empty() {
+            assert!(
+                start.elapsed() < LONG_TIME_LIMIT,
+                "processes were not killed",
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    // The method used to kill the processes can only be checked when events
+    // are traced.
+    fn trace<F, T>(&self, wait_fn: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        #[cfg(feature = "tracing")]
+        {
+            let recorder = Recorder::default();
+            let result =
+                tracing::subscriber::with_default(recorder.clone(), wait_fn);
+            let method = if self.0.join("cgroup.kill").exists() {
+                "cgroup.kill"
+            } else {
+                "cgroup.freeze"
+            };
+            assert!(recorder.contains("method", &format!("{:?}", method)));
+            result
+        }
+        #[cfg(not(feature = "tracing"))]
+        wait_fn()
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        // Killed processes might not have exited yet.
+        let start = Instant::now();
+        while fs::remove_dir(&self.0).is_err()
+            && start.elapsed() < LONG_TIME_LIMIT
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[test]
+fn test_terminate_process_tree() -> io::Result<()> {
+    let Some(cgroup) = Cgroup::new("tree")? else {
+        return Ok(());
+    };
+
+    let mut process = cgroup.spawn(
+        Command::new("sh")
+            .arg("-c")
+            .arg("while true; do sleep 60 & done"),
+    )?;
+    let result = cgroup.trace(|| {
+        process
+            .controlled()
+            .cgroup(&cgroup.0)
+            .time_limit(Duration::from_secs(1))
+            .strict_errors()
+            .terminate_for_timeout()
+            .terminate_process_tree()
+            .wait()
+    })?;
+    assert_eq!(None, result);
+
+    cgroup.assert_empty()
+}
+
+#[test]
+fn test_terminate_for_timeout() -> io::Result<()> {
+    let Some(cgroup) = Cgroup::new("timeout")? else {
+        return Ok(());
+    };
+
+    // Descendants keep the pipe open, so the output could not be read if
+    // they were not killed.
+    let process = cgroup.spawn(
+        Command::new("sh")
+            .arg("-c")
+            .arg("sleep 60 & sleep 60")
+            .stdout(Stdio::piped()),
+    )?;
+    let start = Instant::now();
+    let output = cgroup.trace(|| {
+        process
+            .controlled_with_output()
+            .cgroup(&cgroup.0)
+            .time_limit(Duration::from_secs(1))
+            .strict_errors()
+            .terminate_for_timeout()
+            .wait()
+    })?;
+    assert_eq!(None, output);
+    assert!(start.elapsed() < LONG_TIME_LIMIT);
+
+    cgroup.assert_empty()
+}
+
+#[test]
+fn test_cgroup_opt_in() -> io::Result<()> {
+    let Some(cgroup) = Cgroup::new("opt_in")? else {
+        return Ok(());
+    };
+
+    // Without a cgroup, the process must be a process group leader, even if
+    // it is in a different cgroup.
+    let mut process =
+        cgroup.spawn(Command::new("perl").arg("-e").arg("sleep"))?;
+    let result = process
+        .controlled()
+        .time_limit(LONG_TIME_LIMIT)
+        .terminate_for_timeout()
+        .terminate_process_tree()
+        .wait();
+    assert_eq!(
+        io::ErrorKind::InvalidInput,
+        result.expect_err("missing error").kind(),
+    );
+
+    let mut other_process =
+        Command::new("perl").arg("-e").arg("sleep").spawn()?;
+    let result = other_process
+        .controlled()
+        .cgroup(&cgroup.0)
+        .time_limit(LONG_TIME_LIMIT)
+        .terminate_for_timeout()
+        .wait();
+    assert_eq!(
+        io::ErrorKind::InvalidInput,
+        result.expect_err("missing error").kind(),
+    );
+
+    for mut process in [process, other_process] {
+        process.kill()?;
+        let _ = process.wait()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_pausable() -> io::Result<()> {
+    let Some(cgroup) = Cgroup::new("pause")? else {
+        return Ok(());
+    };
+
+    let handle = PauseHandle::new();
+    handle.pause();
+    let resumer = {
+        let handle = handle.clone();
+        let events = cgroup.0.join("cgroup.events");
+        thread::spawn(move || -> io::Result<()> {
+            let start = Instant::now();
+            while !fs::read_to_string(&events)?.contains("frozen 1") {
+                assert!(
+                    start.elapsed() < LONG_TIME_LIMIT,
+                    "cgroup was not frozen",
+                );
+                thread::sleep(Duration::from_millis(10));
+            }
+            handle.resume();
+            Ok(())
+        })
+    };
+
+    let exit_status = cgroup
+        .spawn(Command::new("perl").arg("-e").arg("sleep 1"))?
+        .controlled()
+        .cgroup(&cgroup.0)
+        .pausable(&handle)
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out");
+    assert!(exit_status.success());
+    resumer.join().expect("thread panicked")
+}
@@ -15,6 +15,9 @@ use process_control::ExitStatus;
 mod imp;
 pub(super) use imp::Handle;
 
+#[cfg(feature = "tracing")]
+pub(super) mod recorder;
+
 pub(super) const SHORT_TIME_LIMIT: Duration = Duration::from_secs(2);
 
 pub(super) const LONG_TIME_LIMIT: Duration = Duration::from_secs(5);
@@ -0,0 +1,59 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+use tracing::Event;
+use tracing::Metadata;
+use tracing::Subscriber;
+
+#[derive(Default)]
+struct Fields(Vec<(String, String)>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().to_owned(), format!("{:?}", value)));
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Recorder(Arc<Mutex<Fields>>);
+
+impl Recorder {
+    pub(crate) fn contains(&self, name: &str, value: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .0
+            .iter()
+            .any(|(x, y)| x == name && y == value)
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        span.record(&mut *self.0.lock().unwrap());
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+        values.record(&mut *self.0.lock().unwrap());
+    }
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        event.record(&mut *self.0.lock().unwrap());
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
@@ -1,11 +1,22 @@
 use std::io;
+use std::io::Write;
+use std::mem;
+use std::process;
 use std::process::Command;
 use std::process::Stdio;
+use std::sync::mpsc;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
+use process_control::filters;
+use process_control::Capture;
 use process_control::ChildExt;
 use process_control::Control;
+use process_control::Event;
+use process_control::PipeFilter;
 
 #[macro_use]
 mod common;
@@ -103,3 +114,889 @@ fn test_large_output() -> io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_wait_scoped() -> io::Result<()> {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+
+    let filtered = Arc::new(AtomicBool::new(false));
+    let mut stdout: Vec<u8> = Vec::new();
+    let mut stderr_length = 0;
+    let output = Command::new("perl")
+        .arg("-e")
+        .arg("print 'a' x 65536; print STDERR 'b' x 65536")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .stdout_filter({
+            let filtered = Arc::clone(&filtered);
+            move |_| {
+                filtered.store(true, Ordering::Relaxed);
+                Ok(true)
+            }
+        })
+        .wait_scoped(
+            |x| {
+                stdout.extend(x);
+                Ok(false)
+            },
+            |x| {
+                stderr_length += x.len();
+                Ok(true)
+            },
+        )?
+        .expect("process timed out");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"", &*output.stdout);
+    assert_eq!(vec![b'b'; 65536], output.stderr);
+    assert_eq!(vec![b'a'; 65536], stdout);
+    assert_eq!(65536, stderr_length);
+    assert!(!filtered.load(Ordering::Relaxed));
+
+    // The process must be terminated even without [terminate_for_timeout].
+    let mut calls = 0;
+    let output = Command::new("perl")
+        .arg("-e")
+        .arg("sleep")
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(Duration::from_secs(1))
+        .strict_errors()
+        .wait_scoped(
+            |_| {
+                calls += 1;
+                Ok(true)
+            },
+            |_| unreachable!(),
+        )?;
+    assert_eq!(None, output);
+    assert_eq!(0, calls);
+
+    Ok(())
+}
+
+#[test]
+fn test_borrowed_output() -> io::Result<()> {
+    let mut process = Command::new("perl")
+        .arg("-e")
+        .arg("$| = 1; print 'foo'; sleep 2; print 'bar'")
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let output = process
+        .controlled_with_output_mut()
+        .time_limit(Duration::from_millis(500))
+        .strict_errors()
+        .wait()?;
+    assert_eq!(None, output);
+    assert!(process.stdout.is_none());
+
+    let exit_status = process
+        .controlled()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out");
+    assert_eq!(Some(0), exit_status.code());
+
+    Ok(())
+}
+
+#[test]
+fn test_borrowed_filter() -> io::Result<()> {
+    let mut process = Command::new("perl")
+        .arg("-e")
+        .arg("print 'a' x 65536; print STDERR 'b'")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let length = Arc::new(Mutex::new(0));
+    let exit_status = process
+        .controlled()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .stdout_filter({
+            let length = Arc::clone(&length);
+            move |x| {
+                *length.lock().unwrap() += x.len();
+                Ok(true)
+            }
+        })
+        .wait()?
+        .expect("process timed out");
+    assert_eq!(Some(0), exit_status.code());
+    assert_eq!(65536, *length.lock().unwrap());
+    assert!(process.stdout.is_none());
+    assert!(process.stderr.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_first_byte() -> io::Result<()> {
+    const DELAY: Duration = Duration::from_millis(500);
+
+    // The delay starts when stdin is closed by [Control::wait_with_report].
+    let start = Instant::now();
+    let report = Command::new("perl")
+        .arg("-e")
+        .arg("<STDIN>; select undef, undef, undef, $ARGV[0]; print 'foo'")
+        .arg("--")
+        .arg(DELAY.as_secs_f64().to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .stdout_filter(filters::timestamped(start, |time, _| {
+            assert!(time >= DELAY);
+            Ok(false)
+        }))
+        .wait_with_report()?;
+    let output = report.result.expect("process timed out");
+    assert_eq!(Some(0), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert!(report
+        .stdout_first_byte
+        .is_some_and(|x| (DELAY..LONG_TIME_LIMIT).contains(&x)));
+    assert_eq!(None, report.stderr_first_byte);
+
+    Ok(())
+}
+
+#[test]
+fn test_filters() -> io::Result<()> {
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+
+    const OUTPUT: &[u8] = b"\x1B[1mfoo\x1B[0m\nbar\r\n\n\x1B]0;title\x07baz";
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let head = Arc::new(Mutex::new(Vec::new()));
+    let counter = Arc::new(AtomicU64::new(0));
+    let (sender, receiver) = mpsc::channel();
+    let writer = SharedWriter::default();
+    let output = Command::new("perl")
+        .arg("-e")
+        .arg(r#"$| = 1; print "\e[1mfoo\e[0m\nb"; print "ar\r\n\n\e]0;title\abaz";"#)
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .stdout_filter(filters::chain(
+            filters::chain(
+                filters::tee(writer.clone()),
+                filters::to_channel(sender),
+            ),
+            filters::chain(
+                filters::chain(
+                    filters::count(Arc::clone(&counter)),
+                    filters::strip_ansi(filters::lines({
+                        let lines = Arc::clone(&lines);
+                        move |x| {
+                            lines.lock().unwrap().push(x.to_owned());
+                            Ok(())
+                        }
+                    })),
+                ),
+                filters::limit(6, {
+                    let head = Arc::clone(&head);
+                    move |x| {
+                        head.lock().unwrap().extend(x);
+                        Ok(true)
+                    }
+                }),
+            ),
+        ))
+        .wait()?
+        .expect("process timed out");
+    assert_eq!(Some(0), output.status.code());
+    // Reads after the one that exceeded the limit are rejected.
+    assert!(output.stdout.len() >= 6);
+    assert!(OUTPUT.starts_with(&output.stdout));
+    assert_eq!(&OUTPUT[..6], &**head.lock().unwrap());
+
+    assert_eq!(OUTPUT, &**writer.0.lock().unwrap());
+    assert_eq!(OUTPUT, &*receiver.into_iter().flatten().collect::<Vec<_>>());
+    assert_eq!(OUTPUT.len() as u64, counter.load(Ordering::Relaxed));
+    assert_eq!([&b"foo"[..], b"bar", b"", b"baz"], **lines.lock().unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn test_limit() -> io::Result<()> {
+    const OUTPUT: &[u8] = b"\x1B[31mfoobar";
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<u8>>>);
+
+    impl Recorder {
+        fn filter(&self) -> impl PipeFilter {
+            let buffer = Arc::clone(&self.0);
+            move |x| {
+                buffer.lock().unwrap().extend(x);
+                Ok(true)
+            }
+        }
+
+        fn take(&self) -> Vec<u8> {
+            mem::take(&mut self.0.lock().unwrap())
+        }
+    }
+
+    fn run<F>(filter: F, limit: Option<usize>) -> io::Result<Vec<u8>>
+    where
+        F: PipeFilter,
+    {
+        let mut control = Command::new("perl")
+            .arg("-e")
+            .arg(r#"print "\e[31mfoobar""#)
+            .stdout(Stdio::piped())
+            .spawn()?
+            .controlled_with_output()
+            .time_limit(LONG_TIME_LIMIT)
+            .strict_errors()
+            .terminate_for_timeout()
+            .stdout_filter(filter);
+        if let Some(limit) = limit {
+            control = control.stdout_limit(limit);
+        }
+        let output = control.wait()?.expect("process timed out");
+        assert_eq!(Some(0), output.status.code());
+        Ok(output.stdout)
+    }
+
+    let first = Recorder::default();
+    let second = Recorder::default();
+
+    let stdout =
+        run(filters::strip_ansi(filters::limit(3, first.filter())), None)?;
+    assert_eq!(OUTPUT, &*stdout);
+    assert_eq!(b"foo", &*first.take());
+
+    let stdout = run(
+        filters::chain(filters::limit(3, first.filter()), second.filter()),
+        None,
+    )?;
+    assert_eq!(OUTPUT, &*stdout);
+    assert_eq!(&OUTPUT[..3], &*first.take());
+    assert_eq!(OUTPUT, &*second.take());
+
+    let stdout = run(filters::strip_ansi(first.filter()), Some(7))?;
+    assert_eq!(&OUTPUT[..7], &*stdout);
+    assert_eq!(b"fo", &*first.take());
+
+    Ok(())
+}
+
+#[test]
+fn test_capture() -> io::Result<()> {
+    const SCRIPT: &str = r"
+        my $output = 'head' . ('x' x $ARGV[0]) . 'tail';
+        print $output;
+        print STDERR $output;
+    ";
+
+    let output = Command::new("perl")
+        .arg("-e")
+        .arg(SCRIPT)
+        .arg("--")
+        .arg("100000")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .stdout_capture(Capture::HeadTail(4, 4))
+        .stderr_capture(Capture::Tail(4))
+        .wait()?
+        .expect("process timed out");
+
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(
+        &b"head\n[100000 of 100008 bytes omitted]\ntail"[..],
+        output.stdout,
+    );
+    assert_eq!(b"tail", &*output.stderr);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extra_pipes() -> io::Result<()> {
+    use process_control::CommandExt;
+
+    let mut command = Command::new("perl");
+    let _ = command
+        .arg("-e")
+        .arg(
+            r"open(my $fd3, '>&=', 3) or die;
+            open(my $fd4, '>&=', 4) or die;
+            print $fd3 'foo';
+            print $fd4 'bar';
+            print 'baz';",
+        )
+        .stdout(Stdio::piped());
+    let fd3 = command.extra_pipe(3)?;
+    let fd4 = command.extra_pipe(4)?;
+
+    let report = command
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .extra_pipe(fd3, |_| Ok(true))
+        .extra_pipe(fd4, |_| Ok(false))
+        .wait_with_report()?;
+    let output = report.result.expect("process timed out");
+
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"baz", &*output.stdout);
+    assert_eq!([&b"foo"[..], b""], *report.extra_pipes);
+
+    let result = command.spawn();
+    assert_eq!(
+        Some(libc::EBADF),
+        result.expect_err("missing error").raw_os_error(),
+    );
+
+    let mut command = Command::new("perl");
+    let _ = command.arg("-e").arg("");
+    let fd3 = command.extra_pipe(3)?;
+    let result = command
+        .spawn()?
+        .controlled_with_output()
+        .extra_pipe(fd3, |_| Ok(true))
+        .wait_scoped(|_| Ok(true), |_| Ok(true));
+    assert_eq!(
+        io::ErrorKind::InvalidInput,
+        result.expect_err("missing error").kind(),
+    );
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn test_pty() -> io::Result<()> {
+    use process_control::CommandExt;
+
+    let mut command = Command::new("sh");
+    let _ = command
+        .arg("-c")
+        .arg("test -t 0 && test -t 1 && test -t 2 && stty size >&2");
+    let pty = command.pty(24, 100)?;
+
+    let output = command
+        .spawn()?
+        .controlled_with_output()
+        .pty(pty)
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .wait()?
+        .expect("process timed out");
+
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"24 100\r\n", &*output.stdout);
+    assert_eq!(b"", &*output.stderr);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_session() -> io::Result<()> {
+    use process_control::Session;
+
+    let process = Command::new("perl")
+        .arg("-e")
+        .arg(
+            r"$| = 1;
+            print '> ';
+            while (my $line = <STDIN>) {
+                print uc $line, '> ';
+            }",
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut session = process
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .session()?;
+
+    let timeout = Duration::from_secs(5);
+    assert_eq!(Some(&b"> "[..]), session.expect(b"> ", timeout)?.as_deref());
+    for input in ["foo", "bar"] {
+        assert_eq!(Some(()), session.send(format!("{}\n", input).as_bytes())?);
+        let output = session.expect(b"> ", timeout)?;
+        assert_eq!(
+            Some(format!("{}\n> ", input.to_uppercase()).into_bytes()),
+            output,
+        );
+    }
+    assert_eq!(None, session.expect(b"> ", Duration::from_millis(100))?);
+
+    let output = session.wait()?.expect("process timed out");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"", &*output.stdout);
+
+    let mut session = Command::new("perl")
+        .arg("-e")
+        .arg("sleep")
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(Duration::from_secs(1))
+        .strict_errors()
+        .terminate_for_timeout()
+        .session()?;
+    assert_eq!(None, session.expect(b"\n", LONG_TIME_LIMIT)?);
+    assert_eq!(None, session.wait()?);
+
+    // Writing more than the pipe buffer to stderr must not block the process.
+    let mut session = Command::new("perl")
+        .arg("-e")
+        .arg(r"$| = 1; print STDERR 'a' x 1_000_000; print '> '")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .session()?;
+    assert_eq!(Some(&b"> "[..]), session.expect(b"> ", timeout)?.as_deref());
+
+    let output = session.wait()?.expect("process timed out");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"", &*output.stdout);
+    assert_eq!(vec![b'a'; 1_000_000], output.stderr);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_soft_time_limit() -> io::Result<()> {
+    let output = Command::new("perl")
+        .arg("-e")
+        .arg(
+            r#"$| = 1;
+            my $signaled;
+            $SIG{USR1} = sub { print "dump\n"; $signaled = 1 };
+            sleep 1 until $signaled;
+            print "done\n";"#,
+        )
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .soft_time_limit(Duration::from_millis(500), |pid| {
+            let status = Command::new("kill")
+                .arg("-USR1")
+                .arg(pid.to_string())
+                .status()?;
+            assert!(status.success());
+            Ok(())
+        })
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"dump\ndone\n", &*output.stdout);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_send_signal() -> io::Result<()> {
+    use std::os::unix::process::ExitStatusExt;
+
+    const SIGTERM: i32 = 15;
+
+    let mut process = Command::new("perl").arg("-e").arg("sleep").spawn()?;
+    process.send_signal(SIGTERM)?;
+    assert_eq!(Some(SIGTERM), process.wait()?.signal());
+
+    let error = process
+        .send_signal(SIGTERM)
+        .expect_err("signal sent to reaped process");
+    assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+    assert!(error.to_string().contains("signal: 15"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pausable() -> io::Result<()> {
+    use process_control::PauseHandle;
+
+    const PAUSE_TIME: Duration = Duration::from_secs(2);
+
+    let handle = PauseHandle::new();
+    let resumer = {
+        let handle = handle.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            while !handle.is_paused() {
+                assert!(start.elapsed() < LONG_TIME_LIMIT, "not paused");
+                thread::sleep(Duration::from_millis(10));
+            }
+            thread::sleep(PAUSE_TIME);
+            handle.resume();
+        })
+    };
+
+    // The process is paused after its first write, which must happen before
+    // the time limit is checked again.
+    let (sender, receiver) = mpsc::channel();
+    let exit_status = Command::new("perl")
+        .arg("-e")
+        .arg(
+            r#"$| = 1;
+            for (1 .. 5) { print "a"; select(undef, undef, undef, 0.1) }"#,
+        )
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled()
+        .stdout_filter({
+            let handle = handle.clone();
+            let mut paused = false;
+            move |_| {
+                if !paused {
+                    handle.pause();
+                    paused = true;
+                }
+                sender.send(Instant::now()).expect("receiver dropped");
+                Ok(true)
+            }
+        })
+        .pausable(&handle)
+        .exclude_paused_time()
+        .time_limit(PAUSE_TIME)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out");
+    assert!(exit_status.success());
+    resumer.join().expect("thread panicked");
+
+    let reads: Vec<_> = receiver.try_iter().collect();
+    assert!(reads
+        .windows(2)
+        .any(|x| x[1].saturating_duration_since(x[0]) >= PAUSE_TIME / 2));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_forward_signals() -> io::Result<()> {
+    use std::mem::MaybeUninit;
+    use std::ptr;
+
+    use libc::SIGHUP;
+    use libc::SIG_DFL;
+
+    fn is_caught() -> bool {
+        let mut action = MaybeUninit::uninit();
+        assert_eq!(0, unsafe {
+            libc::sigaction(SIGHUP, ptr::null(), action.as_mut_ptr())
+        });
+        unsafe { action.assume_init() }.sa_sigaction != SIG_DFL
+    }
+
+    let process = Command::new("perl")
+        .arg("-e")
+        .arg(
+            r#"$| = 1;
+            $SIG{HUP} = sub { print "received\n"; exit };
+            sleep 1 while 1;"#,
+        )
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let signaler = thread::spawn(|| {
+        let start = Instant::now();
+        while !is_caught() {
+            assert!(start.elapsed() < LONG_TIME_LIMIT, "signal not caught");
+            thread::sleep(Duration::from_millis(1));
+        }
+        let pid = process::id().try_into().expect("invalid pid");
+        assert_eq!(0, unsafe { libc::kill(pid, SIGHUP) });
+    });
+    let output = process
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .forward_signals(&[SIGHUP], LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out");
+    signaler.join().expect("thread panicked");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"received\n", &*output.stdout);
+
+    // The default action is restored afterward.
+    assert!(!is_caught());
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn test_parent_death_signal() -> io::Result<()> {
+    use process_control::CommandExt;
+
+    const SIGKILL: i32 = 9;
+
+    let process = thread::spawn(|| {
+        Command::new("perl")
+            .arg("-e")
+            .arg("sleep")
+            .parent_death_signal(SIGKILL)
+            .spawn()
+    })
+    .join()
+    .expect("thread panicked")?;
+    let exit_status = process
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out")
+        .status;
+    assert_eq!(Some(SIGKILL), exit_status.signal());
+
+    let result = Command::new("perl").parent_death_signal(-1).spawn();
+    assert_eq!(
+        Some(libc::EINVAL),
+        result.expect_err("invalid signal was set").raw_os_error(),
+    );
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn assert_killed(pid: u32) -> io::Result<()> {
+    use std::fs;
+
+    // The process will remain a zombie until it is reaped by its new parent.
+    let start = Instant::now();
+    loop {
+        match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) if !stat.contains(") Z ") => {}
+            Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                return Err(error);
+            }
+            _ => return Ok(()),
+        }
+        assert!(start.elapsed() < LONG_TIME_LIMIT, "process was not killed");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn test_leftover_processes() -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    let report = Command::new("sh")
+        .arg("-c")
+        .arg("sleep 60 > /dev/null & echo $!")
+        .process_group(0)
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .leftover_processes(true)
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait_with_report()?;
+    let output = report.result.expect("process timed out");
+    assert!(output.status.success());
+
+    let pid: u32 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .expect("invalid output");
+    assert!(report
+        .leftover_processes
+        .iter()
+        .any(|x| x.pid == pid && x.command == "sleep"));
+
+    assert_killed(pid)
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn test_terminate_process_tree() -> io::Result<()> {
+    use std::env;
+    use std::fs;
+    use std::os::unix::process::CommandExt;
+
+    let pid_file = env::temp_dir()
+        .join(format!("process_control-{}-tree", process::id()));
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(r#"sleep 60 & echo $! > "$0"; wait"#)
+        .arg(&pid_file)
+        .process_group(0)
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(Duration::from_secs(1))
+        .strict_errors()
+        .terminate_for_timeout()
+        .terminate_process_tree()
+        .wait()?;
+    assert!(output.is_none());
+
+    let pid = fs::read_to_string(&pid_file)?;
+    fs::remove_file(&pid_file)?;
+    assert_killed(pid.trim().parse().expect("invalid process identifier"))
+}
+
+#[test]
+fn test_events() -> io::Result<()> {
+    let events = Command::new("perl")
+        .arg("-e")
+        .arg(
+            r"$| = 1;
+            print 'foo';
+            print STDERR 'bar';
+            print 'baz';",
+        )
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .events()?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_status = None;
+    for event in events {
+        assert_eq!(None, exit_status);
+        match event? {
+            Event::Stdout(output) => stdout.extend(output),
+            Event::Stderr(output) => stderr.extend(output),
+            Event::Exited(status) => exit_status = Some(status),
+            event => panic!("unexpected event: {:?}", event),
+        }
+    }
+    assert_eq!(Some(0), exit_status.and_then(|x| x.code()));
+    assert_eq!(b"foobaz", &*stdout);
+    assert_eq!(b"bar", &*stderr);
+
+    let mut events = Command::new("perl")
+        .arg("-e")
+        .arg("sleep")
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(Duration::from_secs(1))
+        .strict_errors()
+        .terminate_for_timeout()
+        .events()?;
+    assert!(matches!(events.next(), Some(Ok(Event::Terminated))));
+    assert!(events.next().is_none());
+
+    Ok(())
+}
+
+#[derive(Clone, Default)]
+struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_writer() -> io::Result<()> {
+    let stdout = SharedWriter::default();
+    let stderr = SharedWriter::default();
+    let output = Command::new("perl")
+        .arg("-e")
+        .arg("print 'foo'; print STDERR 'bar'")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .stdout_to(stdout.clone())
+        .tee_stderr(stderr.clone())
+        .wait()?
+        .expect("process timed out");
+
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(b"", &*output.stdout);
+    assert_eq!(b"bar", &*output.stderr);
+    assert_eq!(b"foo", &**stdout.0.lock().unwrap());
+    assert_eq!(b"bar", &**stderr.0.lock().unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn test_writer_error() -> io::Result<()> {
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Ok(0)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let result = Command::new("perl")
+        .arg("-e")
+        .arg("print 'foo'")
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .stdout_to(FailingWriter)
+        .wait();
+    assert_eq!(
+        io::ErrorKind::WriteZero,
+        result.expect_err("missing error").kind(),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "log")]
+
+use std::io;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Mutex;
+
+use log::kv::Key;
+use log::Level;
+use log::LevelFilter;
+use log::Log;
+use log::Metadata;
+use log::Record;
+use process_control::ChildExt;
+use process_control::Control;
+
+#[allow(unused_macros)]
+mod common;
+use common::LONG_TIME_LIMIT;
+
+type LogRecord = (String, Level, String, Option<u64>, Option<String>);
+
+static RECORDS: Mutex<Vec<LogRecord>> = Mutex::new(Vec::new());
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let pid = record
+            .key_values()
+            .get(Key::from_str("pid"))
+            .and_then(|x| x.to_u64());
+        let program = record
+            .key_values()
+            .get(Key::from_str("program"))
+            .map(|x| x.to_string());
+        RECORDS.lock().unwrap().push((
+            record.target().to_owned(),
+            record.level(),
+            record.args().to_string(),
+            pid,
+            program,
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+fn record(level: Level, line: &str, pid: u64, program: &str) -> LogRecord {
+    (
+        "test".to_owned(),
+        level,
+        line.to_owned(),
+        Some(pid),
+        Some(program.to_owned()),
+    )
+}
+
+#[test]
+fn test_log() -> io::Result<()> {
+    log::set_logger(&Logger).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let mut command = Command::new("perl");
+    let _ = command
+        .arg("-e")
+        .arg(r#"print "foo\nbar"; print STDERR "baz\n""#)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut process = command.spawn()?;
+    let pid = process.id().into();
+
+    let exit_status = process
+        .controlled()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .log_stdout("test", Level::Info, command.get_program())
+        .log_stderr("test", Level::Warn, "foo")
+        .wait()?
+        .expect("process timed out");
+    assert_eq!(Some(0), exit_status.code());
+
+    let mut records = RECORDS.lock().unwrap();
+    records.sort();
+    assert_eq!(
+        [
+            record(Level::Warn, "baz", pid, "foo"),
+            record(Level::Info, "bar", pid, "perl"),
+            record(Level::Info, "foo", pid, "perl"),
+        ],
+        &**records,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+#![cfg(any(target_os = "android", target_os = "linux"))]
+
+use std::io;
+use std::os::raw::c_int;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
+use std::process::Stdio;
+use std::str;
+
+use process_control::ChildExt;
+use process_control::Control;
+
+#[allow(unused_macros)]
+mod common;
+use common::LONG_TIME_LIMIT;
+
+fn is_subreaper() -> bool {
+    let mut subreaper: c_int = 0;
+    assert_eq!(0, unsafe {
+        libc::prctl(libc::PR_GET_CHILD_SUBREAPER, &mut subreaper)
+    });
+    subreaper != 0
+}
+
+// The subreaper attribute applies to the entire process, so this test is in
+// its own file.
+#[test]
+fn test_adopt_orphans() -> io::Result<()> {
+    // Unrelated children must not be killed.
+    let mut unrelated = Command::new("perl").arg("-e").arg("sleep").spawn()?;
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg("read _; sleep 60 & echo $!")
+        .process_group(0)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .adopt_orphans()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out");
+    assert!(output.status.success());
+
+    let pid = str::from_utf8(&output.stdout).expect("invalid output");
+    assert!(!Path::new("/proc").join(pid.trim()).exists());
+    assert!(!is_subreaper());
+
+    assert_eq!(None, unrelated.try_wait()?);
+    unrelated.kill()?;
+    let _ = unrelated.wait()?;
+
+    let mut process = Command::new("perl").arg("-e").arg("sleep").spawn()?;
+    let result = process
+        .controlled()
+        .adopt_orphans()
+        .time_limit(LONG_TIME_LIMIT)
+        .terminate_for_timeout()
+        .wait();
+    assert_eq!(
+        io::ErrorKind::InvalidInput,
+        result.expect_err("missing error").kind(),
+    );
+    process.kill()?;
+    let _ = process.wait()?;
+
+    Ok(())
+}
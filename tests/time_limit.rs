@@ -1,8 +1,15 @@
 use std::io;
 use std::process::Child;
 use std::process::Command;
+use std::process::Stdio;
+use std::sync::mpsc;
 use std::time::Duration;
 
+use process_control::ChildExt;
+use process_control::Control;
+use process_control::Decision;
+use process_control::ExitStatus;
+
 #[macro_use]
 mod common;
 use common::Limit;
@@ -109,3 +116,125 @@ fn test_1() {
         running: false,
     );
 }
+
+#[test]
+fn test_elapsed() -> io::Result<()> {
+    // The process only starts sleeping when stdin is closed by
+    // [Control::wait], since it might otherwise start before timing does.
+    let exit_status = Command::new("perl")
+        .arg("-e")
+        .arg("<STDIN>; sleep $ARGV[0]")
+        .arg("--")
+        .arg(SHORT_TIME_LIMIT.as_secs().to_string())
+        .stdin(Stdio::piped())
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .wait()?
+        .expect("process timed out")
+        .status;
+    assert_eq!(Some(0), exit_status.code());
+    assert!(exit_status
+        .elapsed()
+        .is_some_and(|x| (SHORT_TIME_LIMIT..LONG_TIME_LIMIT).contains(&x)));
+
+    let std_exit_status: ExitStatus = exit_status.into_std_lossy().into();
+    assert_eq!(exit_status, std_exit_status);
+    assert_eq!(None, std_exit_status.elapsed());
+    assert_eq!(
+        format!("{:?}", exit_status),
+        format!("{:?}", std_exit_status),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_on_tick_extend() -> io::Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(50);
+
+    let mut ticks = 0;
+    let exit_status = common::create_time_limit_command(SHORT_TIME_LIMIT)
+        .spawn()?
+        .controlled_with_output()
+        .time_limit(INTERVAL)
+        .strict_errors()
+        .terminate_for_timeout()
+        .on_tick(INTERVAL, move |progress| {
+            ticks += 1;
+            assert!(progress.elapsed >= INTERVAL * ticks);
+            assert_eq!(0, progress.stdout_bytes);
+            Decision::ExtendTimeLimit(INTERVAL * 2)
+        })
+        .wait()?
+        .expect("process timed out")
+        .status;
+    assert_eq!(Some(0), exit_status.code());
+
+    Ok(())
+}
+
+#[test]
+fn test_on_tick_terminate() -> io::Result<()> {
+    let mut process =
+        common::create_time_limit_command(LONG_TIME_LIMIT).spawn()?;
+    let exit_status = process
+        .controlled()
+        .strict_errors()
+        .on_tick(Duration::from_millis(50), |_| Decision::Terminate)
+        .wait()?;
+    assert_eq!(None, exit_status);
+    assert!(process.try_wait()?.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_on_tick_overflow() -> io::Result<()> {
+    let exit_status = common::create_time_limit_command(Duration::ZERO)
+        .spawn()?
+        .controlled()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .on_tick(Duration::MAX, |_| panic!("tick function was called"))
+        .wait()?
+        .expect("process timed out");
+    assert_eq!(Some(0), exit_status.code());
+
+    Ok(())
+}
+
+#[test]
+fn test_before_terminate() -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut process =
+        common::create_time_limit_command(LONG_TIME_LIMIT).spawn()?;
+    let exit_status = process
+        .controlled()
+        .time_limit(Duration::from_millis(100))
+        .strict_errors()
+        .terminate_for_timeout()
+        .before_terminate(move |pid| {
+            sender.send(pid).expect("receiver dropped");
+            Ok(())
+        })
+        .wait()?;
+    assert_eq!(None, exit_status);
+    assert_eq!(Ok(process.id()), receiver.try_recv());
+
+    let exit_status = common::create_time_limit_command(Duration::ZERO)
+        .spawn()?
+        .controlled()
+        .time_limit(LONG_TIME_LIMIT)
+        .strict_errors()
+        .terminate_for_timeout()
+        .before_terminate(|_| panic!("process was terminated"))
+        .wait()?
+        .expect("process timed out");
+    assert_eq!(Some(0), exit_status.code());
+
+    Ok(())
+}
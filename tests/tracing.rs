@@ -0,0 +1,38 @@
+#![cfg(feature = "tracing")]
+
+use std::io;
+use std::process::Command;
+use std::time::Duration;
+
+use process_control::ChildExt;
+use process_control::Control;
+
+#[allow(unused_macros)]
+mod common;
+use common::recorder::Recorder;
+
+#[test]
+fn test_tracing() -> io::Result<()> {
+    let mut process = Command::new("perl").arg("-e").arg("sleep").spawn()?;
+    let pid = process.id().to_string();
+
+    let recorder = Recorder::default();
+    let result = tracing::subscriber::with_default(recorder.clone(), || {
+        process
+            .controlled()
+            .time_limit(Duration::from_secs(1))
+            .strict_errors()
+            .terminate_for_timeout()
+            .wait()
+    })?;
+    assert_eq!(None, result);
+
+    assert!(recorder.contains("pid", &pid));
+    assert!(recorder.contains("time_limit", "Some(1s)"));
+    assert!(recorder.contains("message", "exceeded time limit"));
+    assert!(recorder.contains("message", "terminating process"));
+    assert!(recorder.contains("message", "reaped process"));
+    assert!(recorder.contains("outcome", "\"terminated\""));
+
+    Ok(())
+}